@@ -1,6 +1,8 @@
 use crate::change_workspace;
+use crate::config::DEFAULT_MODE;
 use crate::display::get_primary_display;
 use crate::event::Event;
+use crate::hot_key_manager;
 use crate::is_visible_workspace;
 use crate::tile_grid::TileGrid;
 use crate::util;
@@ -52,6 +54,7 @@ use winapi::um::winuser::SetCursor;
 use winapi::um::winuser::ShowWindow;
 use winapi::um::winuser::TranslateMessage;
 use winapi::um::winuser::DT_CENTER;
+use winapi::um::winuser::DT_END_ELLIPSIS;
 use winapi::um::winuser::DT_SINGLELINE;
 use winapi::um::winuser::DT_VCENTER;
 use winapi::um::winuser::IDC_ARROW;
@@ -72,12 +75,14 @@ lazy_static! {
     pub static ref WINDOWS: Mutex<HashMap<i32, i32>> = Mutex::new(HashMap::new());
     pub static ref FONT: Mutex<i32> = Mutex::new(0);
     pub static ref REDRAW_REASON: Mutex<RedrawAppBarReason> = Mutex::new(RedrawAppBarReason::Time);
+    static ref CONFIG_ERROR: Mutex<Option<String>> = Mutex::new(None);
 }
 
 #[derive(Copy, Clone, Debug)]
 pub enum RedrawAppBarReason {
     Time,
     Workspace,
+    Mode,
 }
 
 unsafe extern "system" fn window_cb(
@@ -131,6 +136,11 @@ unsafe extern "system" fn window_cb(
                     now.elapsed().expect("Failed to get systemtime").as_millis()
                 )
             }
+            RedrawAppBarReason::Mode => {
+                if draw_mode(hwnd).is_err() {
+                    error!("Failed to draw mode");
+                }
+            }
         }
 
         EndPaint(hwnd, &paint);
@@ -328,6 +338,7 @@ pub fn create() -> Result<(), util::WinApiResultError> {
 
             draw_workspaces(window_handle);
             draw_datetime(window_handle).expect("Failed to draw datetime");
+            draw_mode(window_handle).expect("Failed to draw mode");
             ShowWindow(window_handle, SW_SHOW);
 
             let mut msg: MSG = MSG::default();
@@ -396,6 +407,7 @@ pub fn show() {
             ShowWindow(hwnd as HWND, SW_SHOW);
             draw_workspaces(hwnd as HWND);
             draw_datetime(hwnd as HWND).expect("Failed to draw datetime");
+            draw_mode(hwnd as HWND).expect("Failed to draw mode");
         }
     }
 }
@@ -473,6 +485,87 @@ pub fn draw_datetime(hwnd: HWND) -> Result<(), util::WinApiResultError> {
     Ok(())
 }
 
+/// Shows why the last reload of the config failed until a reload succeeds
+pub fn set_config_error(error: Option<String>) {
+    let mut current = CONFIG_ERROR.lock().unwrap();
+
+    if *current == error {
+        return;
+    }
+
+    *current = error;
+
+    CHANNEL
+        .sender
+        .clone()
+        .send(Event::RedrawAppBar(RedrawAppBarReason::Mode))
+        .expect("Failed to send redraw-app-bar event");
+}
+
+/// Draws the hint of a pending key sequence, the error of the last config reload or the name of
+/// the active keybinding mode to the right of the workspaces. Nothing is shown while the default
+/// mode is active.
+pub fn draw_mode(hwnd: HWND) -> Result<(), util::WinApiResultError> {
+    if !hwnd.is_null() {
+        let mut rect = RECT::default();
+        let height = *HEIGHT.lock().unwrap();
+        let config_error = CONFIG_ERROR.lock().unwrap().clone();
+        let text = hot_key_manager::get_hint()
+            .or_else(|| config_error.map(|e| format!("config error: {}", e)))
+            .unwrap_or_else(|| {
+                let mode = hot_key_manager::get_mode();
+
+                if mode == DEFAULT_MODE {
+                    String::new()
+                } else {
+                    mode
+                }
+            });
+
+        unsafe {
+            util::winapi_nullable_to_result(GetClientRect(hwnd, &mut rect))?;
+
+            // there is space for 10 workspaces in front of the mode
+            rect.left += height * 10 + 10;
+            rect.right = rect.left + (rect.right - rect.left) / 4;
+
+            let hdc = util::winapi_ptr_to_result(GetDC(hwnd))?;
+            let app_bar_bg = CONFIG.lock().unwrap().app_bar_bg;
+            let brush = CreateSolidBrush(app_bar_bg as u32);
+
+            FillRect(hdc, &rect, brush);
+            DeleteObject(brush as *mut std::ffi::c_void);
+
+            if !text.is_empty() {
+                set_font(hdc);
+
+                SetBkMode(hdc, TRANSPARENT as i32);
+
+                if CONFIG.lock().unwrap().light_theme {
+                    SetTextColor(hdc, 0x00333333);
+                } else {
+                    SetTextColor(hdc, 0x00ffffff);
+                }
+
+                let text_len = text.len() as i32;
+                let c_text = CString::new(text).unwrap();
+
+                util::winapi_nullable_to_result(DrawTextA(
+                    hdc,
+                    c_text.as_ptr(),
+                    text_len,
+                    &mut rect,
+                    DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS,
+                ))?;
+            }
+
+            ReleaseDC(hwnd, hdc);
+        }
+    }
+
+    Ok(())
+}
+
 pub fn draw_workspace(
     hwnd: HWND,
     idx: i32,
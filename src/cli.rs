@@ -0,0 +1,300 @@
+use crate::ipc::client::Client;
+use crate::ipc::protocol::{Request, Response, WindowInfo, WorkspaceInfo, WorkspaceTree};
+use crate::launcher;
+use serde::Serialize;
+use winapi::um::wincon::AttachConsole;
+use winapi::um::wincon::ATTACH_PARENT_PROCESS;
+
+const USAGE: &str = "usage:
+  wwm [--config <path>]
+  wwm recover
+  wwm msg <command>
+  wwm query workspaces|tree|focused [--json]
+  wwm subscribe [<event>...]";
+
+/// The exit codes of the subcommands
+const SUCCESS: i32 = 0;
+const REQUEST_FAILED: i32 = 1;
+const INVALID_USAGE: i32 = 2;
+const NOT_CONNECTED: i32 = 3;
+
+#[derive(Serialize)]
+struct Focused {
+    workspace: Option<i32>,
+    window: Option<WindowInfo>,
+}
+
+/// Takes `--config <path>` out of the arguments. Returns the exit code if the path is missing.
+pub fn take_config_path(args: &mut Vec<String>) -> Result<Option<String>, i32> {
+    let idx = match args.iter().position(|a| a == "--config") {
+        Some(idx) => idx,
+        None => return Ok(None),
+    };
+
+    if idx + 1 >= args.len() {
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+
+        eprintln!("error: --config needs a path\n{}", USAGE);
+        return Err(INVALID_USAGE);
+    }
+
+    let path = args.remove(idx + 1);
+    args.remove(idx);
+
+    Ok(Some(path))
+}
+
+/// Runs the subcommand if the arguments contain one. Returns the exit code.
+pub fn run(args: &[String]) -> Option<i32> {
+    let subcommand = args.get(0)?.as_str();
+
+    if !["msg", "query", "subscribe"].contains(&subcommand) {
+        return None;
+    }
+
+    // release builds don't get a console of their own
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+
+    let mut client = match Client::connect() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("error: can't connect to wwm, is it running? ({})", e);
+            return Some(NOT_CONNECTED);
+        }
+    };
+
+    let result = match subcommand {
+        "msg" => msg(&mut client, &args[1..]),
+        "query" => query(&mut client, &args[1..]),
+        _ => subscribe(&mut client, &args[1..]),
+    };
+
+    Some(match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+
+            // anything but the connection itself, e.g. an answer that can't be read
+            match e.downcast_ref::<std::io::Error>() {
+                Some(_) => NOT_CONNECTED,
+                None => REQUEST_FAILED,
+            }
+        }
+    })
+}
+
+/// Prints the error of a failed request and returns the exit code
+fn check(response: &Response) -> Result<(), i32> {
+    if response.success {
+        return Ok(());
+    }
+
+    match &response.error {
+        Some(error) => eprintln!("error: {}", error.message),
+        None => eprintln!("error: the request failed"),
+    }
+
+    Err(REQUEST_FAILED)
+}
+
+/// Puts the arguments back together the way the command parser splits them, so the quoting of
+/// the shell survives. A single argument is the whole command, e.g. `wwm msg "focus left; close"`.
+fn join_args(args: &[String]) -> String {
+    if let [command] = args {
+        return command.clone();
+    }
+
+    args.iter()
+        .map(|arg| match arg.as_str() {
+            ";" => arg.clone(),
+            // the command parser also reads single quotes and `;`
+            _ if arg.contains(['\'', ';']) => launcher::wrap_in_quotes(arg),
+            _ => launcher::quote_arg(arg),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn msg(client: &mut Client, args: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        return Ok(INVALID_USAGE);
+    }
+
+    let response = client.request(&Request::RunCommand {
+        command: join_args(args),
+    })?;
+
+    Ok(check(&response).err().unwrap_or(SUCCESS))
+}
+
+fn query(client: &mut Client, args: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    let json = args.iter().any(|a| a == "--json");
+    let what: Vec<&String> = args.iter().filter(|a| *a != "--json").collect();
+
+    let request = match what.as_slice() {
+        [x] if *x == "workspaces" => Request::GetWorkspaces,
+        [x] if *x == "tree" || *x == "focused" => Request::GetTree,
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(INVALID_USAGE);
+        }
+    };
+
+    let response = client.request(&request)?;
+
+    if let Err(code) = check(&response) {
+        return Ok(code);
+    }
+
+    let data = response.data.unwrap_or_default();
+
+    match what[0].as_str() {
+        "workspaces" => {
+            let workspaces: Vec<WorkspaceInfo> = serde_json::from_value(data)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&workspaces)?);
+            } else {
+                for w in workspaces {
+                    println!("{}", describe_workspace(&w));
+                }
+            }
+        }
+        "tree" => {
+            let tree: Vec<WorkspaceTree> = serde_json::from_value(data)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tree)?);
+            } else {
+                for w in tree.iter().filter(|w| !w.tiles.is_empty()) {
+                    println!("{}", describe_workspace(&w.workspace));
+
+                    for tile in &w.tiles {
+                        println!(
+                            "  {} {} [{}]",
+                            if tile.focused { "*" } else { " " },
+                            tile.title,
+                            tile.class
+                        );
+                    }
+                }
+            }
+        }
+        _ => {
+            let tree: Vec<WorkspaceTree> = serde_json::from_value(data)?;
+            let workspace = tree.into_iter().find(|w| w.workspace.focused);
+            let focused = Focused {
+                workspace: workspace.as_ref().map(|w| w.workspace.id),
+                window: workspace.and_then(|w| w.tiles.into_iter().find(|t| t.focused)),
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&focused)?);
+            } else {
+                if let Some(id) = focused.workspace {
+                    println!("workspace {}", id);
+                }
+                if let Some(window) = focused.window {
+                    println!("{} [{}]", window.title, window.class);
+                }
+            }
+        }
+    }
+
+    Ok(SUCCESS)
+}
+
+fn describe_workspace(workspace: &WorkspaceInfo) -> String {
+    let mut flags = Vec::new();
+
+    if workspace.focused {
+        flags.push("focused");
+    }
+    if workspace.visible {
+        flags.push("visible");
+    }
+    if workspace.fullscreen {
+        flags.push("fullscreen");
+    }
+
+    format!(
+        "workspace {}: {} window(s) {}",
+        workspace.id,
+        workspace.windows,
+        flags.join(" ")
+    )
+}
+
+fn subscribe(client: &mut Client, events: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    let response = client.request(&Request::Subscribe {
+        events: events.to_vec(),
+    })?;
+
+    if let Err(code) = check(&response) {
+        return Ok(code);
+    }
+
+    while let Some(line) = client.read_line()? {
+        println!("{}", line);
+    }
+
+    eprintln!("error: wwm closed the connection");
+
+    Ok(NOT_CONNECTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Command, CommandList};
+
+    fn parse(args: &[&str]) -> Vec<Command> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+
+        join_args(&args).parse::<CommandList>().unwrap().0
+    }
+
+    #[test]
+    fn keeps_arguments_with_spaces_together() {
+        match parse(&["launch", r"C:\Program Files\x.exe", "a b"]).as_slice() {
+            [Command::Launch(spec)] => {
+                assert_eq!(spec.cmd, r#""C:\Program Files\x.exe" "a b""#)
+            }
+            x => panic!("{:?}", x),
+        }
+
+        assert_eq!(
+            parse(&["layout", "two columns"]),
+            vec![Command::ApplyLayout(String::from("two columns"))]
+        );
+    }
+
+    #[test]
+    fn quotes_what_the_parser_treats_as_special() {
+        assert_eq!(
+            parse(&["layout", "it's;mine"]),
+            vec![Command::ApplyLayout(String::from("it's;mine"))]
+        );
+    }
+
+    #[test]
+    fn single_argument_is_the_whole_command() {
+        assert_eq!(
+            parse(&["layout main; undo"]),
+            vec![Command::ApplyLayout(String::from("main")), Command::Undo]
+        );
+    }
+
+    #[test]
+    fn separate_semicolon_separates_commands() {
+        assert_eq!(
+            parse(&["layout", "main", ";", "undo"]),
+            vec![Command::ApplyLayout(String::from("main")), Command::Undo]
+        );
+    }
+}
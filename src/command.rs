@@ -0,0 +1,155 @@
+use crate::hot_key_manager::Direction;
+use crate::launcher::LaunchSpec;
+use crate::tile_grid::SplitDirection;
+use std::str::FromStr;
+
+pub mod parser;
+
+/// An action of the window manager.
+///
+/// Commands are written as text, e.g. `focus left` or `move to workspace 3 --no-follow`, so the
+/// same actions can be used by keybindings and anything else that wants to control wwm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    CloseTile,
+    Quit,
+    ChangeWorkspace(i32),
+    ToggleFloatingMode,
+    ToggleWorkMode,
+    MoveWorkspaceToMonitor(i32),
+    ToggleFullscreen,
+    Launch(LaunchSpec),
+    Focus(Direction),
+    Swap(Direction),
+    /// Changes to the workspace afterwards if `follow` is true
+    MoveToWorkspace {
+        id: i32,
+        follow: bool,
+    },
+    Split(SplitDirection),
+    ApplyLayout(String),
+    Undo,
+    Redo,
+    EnterMode(String),
+    /// Calls a function of the scripts
+    Script(String),
+    ReloadConfig,
+}
+
+impl Command {
+    /// Whether the command changes the arrangement of the windows and can be undone
+    pub fn changes_layout(&self) -> bool {
+        match self {
+            Command::CloseTile
+            | Command::ToggleFloatingMode
+            | Command::ToggleFullscreen
+            | Command::Swap(_)
+            | Command::MoveToWorkspace { .. }
+            | Command::Split(_)
+            | Command::ApplyLayout(_) => true,
+            _ => false,
+        }
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Left => "left",
+        Direction::Right => "right",
+        Direction::Up => "up",
+        Direction::Down => "down",
+    }
+}
+
+/// Quotes the argument if it wouldn't be read back as a single word
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || ";'\"".contains(c)) {
+        arg.to_string()
+    } else if arg.contains('\'') {
+        format!("\"{}\"", arg)
+    } else {
+        format!("'{}'", arg)
+    }
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::CloseTile => write!(f, "close"),
+            Command::Quit => write!(f, "quit"),
+            Command::ChangeWorkspace(id) => write!(f, "workspace {}", id),
+            Command::ToggleFloatingMode => write!(f, "toggle floating"),
+            Command::ToggleWorkMode => write!(f, "toggle work_mode"),
+            Command::MoveWorkspaceToMonitor(monitor) => {
+                write!(f, "move workspace to monitor {}", monitor)
+            }
+            Command::ToggleFullscreen => write!(f, "toggle fullscreen"),
+            Command::Launch(spec) => {
+                write!(f, "launch")?;
+
+                if let Some(id) = spec.workspace {
+                    write!(f, " --workspace {}", id)?;
+                }
+                if let Some(cwd) = &spec.cwd {
+                    write!(f, " --cwd {}", quote(cwd))?;
+                }
+                for (name, value) in &spec.env {
+                    write!(f, " --env {}", quote(&format!("{}={}", name, value)))?;
+                }
+                if spec.shell {
+                    write!(f, " --shell")?;
+                }
+
+                write!(f, " {}", spec.program())
+            }
+            Command::Focus(direction) => write!(f, "focus {}", direction_name(*direction)),
+            Command::Swap(direction) => write!(f, "swap {}", direction_name(*direction)),
+            Command::MoveToWorkspace { id, follow: true } => write!(f, "move to workspace {}", id),
+            Command::MoveToWorkspace { id, follow: false } => {
+                write!(f, "move to workspace {} --no-follow", id)
+            }
+            Command::Split(SplitDirection::Horizontal) => write!(f, "split horizontal"),
+            Command::Split(SplitDirection::Vertical) => write!(f, "split vertical"),
+            Command::ApplyLayout(name) => write!(f, "layout {}", quote(name)),
+            Command::Undo => write!(f, "undo"),
+            Command::Redo => write!(f, "redo"),
+            Command::EnterMode(name) => write!(f, "mode {}", quote(name)),
+            Command::Script(name) => write!(f, "script {}", quote(name)),
+            Command::ReloadConfig => write!(f, "reload"),
+        }
+    }
+}
+
+/// Commands separated by `;` that get executed one after another
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommandList(pub Vec<Command>);
+
+impl CommandList {
+    pub fn changes_layout(&self) -> bool {
+        self.0.iter().any(Command::changes_layout)
+    }
+    pub fn contains(&self, command: &Command) -> bool {
+        self.0.contains(command)
+    }
+}
+
+impl FromStr for CommandList {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parser::parse(text)
+    }
+}
+
+impl std::fmt::Display for CommandList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let commands = self
+            .0
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        write!(f, "{}", commands)
+    }
+}
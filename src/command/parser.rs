@@ -0,0 +1,407 @@
+use super::{Command, CommandList};
+use crate::hot_key_manager::Direction;
+use crate::launcher::LaunchSpec;
+use crate::tile_grid::SplitDirection;
+
+/// The commands and their arguments, used to tell what went wrong
+const USAGE: &[(&str, &str)] = &[
+    ("close", "close"),
+    ("quit", "quit"),
+    ("workspace", "workspace <id>"),
+    (
+        "move",
+        "move to workspace <id> [--no-follow] | move workspace to monitor <monitor>",
+    ),
+    ("toggle", "toggle floating | fullscreen | work_mode"),
+    (
+        "launch",
+        "launch [--workspace <id>] [--cwd <dir>] [--env <name>=<value>] [--shell] <program> [<args>]",
+    ),
+    ("focus", "focus left | right | up | down"),
+    ("swap", "swap left | right | up | down"),
+    ("split", "split horizontal | vertical"),
+    ("layout", "layout <name>"),
+    ("undo", "undo"),
+    ("redo", "redo"),
+    ("mode", "mode <name>"),
+    ("script", "script <function>"),
+    ("reload", "reload"),
+];
+
+fn is_quote(c: char) -> bool {
+    c == '"' || c == '\''
+}
+
+/// Splits the text at every `;` that isn't quoted
+fn split_commands(text: &str) -> Result<Vec<&str>, String> {
+    let mut commands = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+
+    for (idx, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if is_quote(c) => quote = Some(c),
+            None if c == ';' => {
+                commands.push(&text[start..idx]);
+                start = idx + 1;
+            }
+            None => {}
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(format!("missing closing {}", q));
+    }
+
+    commands.push(&text[start..]);
+
+    Ok(commands)
+}
+
+/// Splits a command into words. Quotes group words, e.g. `layout "two columns"`.
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_with(String::new).push(c),
+            None if is_quote(c) => {
+                quote = Some(c);
+                // an empty pair of quotes is still a word
+                word.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    words.extend(word);
+    words
+}
+
+/// Splits off the first word and returns the rest of the text as it is
+fn next_word(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start();
+    let mut quote: Option<char> = None;
+
+    for (idx, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if is_quote(c) => quote = Some(c),
+            None if c.is_whitespace() => {
+                return Some((split_words(&text[..idx]).concat(), &text[idx..]))
+            }
+            None => {}
+        }
+    }
+
+    if text.is_empty() {
+        None
+    } else {
+        Some((split_words(text).concat(), ""))
+    }
+}
+
+/// Reads the options in front of the program, the program itself gets passed on as written,
+/// so its own quoting stays intact
+fn parse_launch(text: &str) -> Result<LaunchSpec, String> {
+    let mut spec = LaunchSpec::default();
+    let mut rest = text;
+
+    while let Some((word, after)) = next_word(rest) {
+        if !word.starts_with("--") {
+            break;
+        }
+
+        if word == "--shell" {
+            spec.shell = true;
+            rest = after;
+            continue;
+        }
+
+        let (value, after) =
+            next_word(after).ok_or(format!("'launch {}' expects a value", word))?;
+
+        match word.as_str() {
+            "--workspace" => spec.workspace = Some(parse_workspace_id(&value)?),
+            "--cwd" => spec.cwd = Some(value),
+            "--env" => {
+                let idx = value.find('=').ok_or(format!(
+                    "'launch --env' expects <name>=<value>, got '{}'",
+                    value
+                ))?;
+
+                spec.env
+                    .push((value[..idx].to_string(), value[idx + 1..].to_string()));
+            }
+            x => return Err(format!("unknown option '{}' of 'launch'", x)),
+        }
+
+        rest = after;
+    }
+
+    spec.cmd = rest.trim().to_string();
+
+    if spec.cmd.is_empty() {
+        return Err("'launch' expects a program".into());
+    }
+
+    Ok(spec)
+}
+
+fn parse_number(name: &str, text: &str) -> Result<i32, String> {
+    text.parse()
+        .map_err(|_| format!("'{}' expects a number, got '{}'", name, text))
+}
+
+pub fn validate_workspace_id(id: i32) -> Result<i32, String> {
+    if !(1..=10).contains(&id) {
+        return Err(format!(
+            "workspace {} doesn't exist, ids go from 1 to 10",
+            id
+        ));
+    }
+
+    Ok(id)
+}
+
+fn parse_workspace_id(text: &str) -> Result<i32, String> {
+    validate_workspace_id(parse_number("workspace", text)?)
+}
+
+fn parse_direction(name: &str, text: &str) -> Result<Direction, String> {
+    match text {
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        x => Err(format!(
+            "'{}' expects left, right, up or down, got '{}'",
+            name, x
+        )),
+    }
+}
+
+fn parse_split_direction(text: &str) -> Result<SplitDirection, String> {
+    match text {
+        "horizontal" => Ok(SplitDirection::Horizontal),
+        "vertical" => Ok(SplitDirection::Vertical),
+        x => Err(format!(
+            "'split' expects horizontal or vertical, got '{}'",
+            x
+        )),
+    }
+}
+
+fn parse_command(text: &str) -> Result<Command, String> {
+    let words = split_words(text);
+    let args: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    Ok(match args.as_slice() {
+        // the first word might be quoted, so its length in the text is unknown
+        ["launch", ..] => {
+            Command::Launch(parse_launch(next_word(text).map_or("", |(_, rest)| rest))?)
+        }
+        ["close"] => Command::CloseTile,
+        ["quit"] => Command::Quit,
+        ["undo"] => Command::Undo,
+        ["redo"] => Command::Redo,
+        ["reload"] => Command::ReloadConfig,
+        ["workspace", id] => Command::ChangeWorkspace(parse_workspace_id(id)?),
+        ["move", "to", "workspace", id] => Command::MoveToWorkspace {
+            id: parse_workspace_id(id)?,
+            follow: true,
+        },
+        ["move", "to", "workspace", id, "--no-follow"] => Command::MoveToWorkspace {
+            id: parse_workspace_id(id)?,
+            follow: false,
+        },
+        ["move", "workspace", "to", "monitor", monitor] => {
+            Command::MoveWorkspaceToMonitor(parse_number("monitor", monitor)?)
+        }
+        ["toggle", "floating"] => Command::ToggleFloatingMode,
+        ["toggle", "fullscreen"] => Command::ToggleFullscreen,
+        ["toggle", "work_mode"] => Command::ToggleWorkMode,
+        ["focus", direction] => Command::Focus(parse_direction("focus", direction)?),
+        ["swap", direction] => Command::Swap(parse_direction("swap", direction)?),
+        ["split", direction] => Command::Split(parse_split_direction(direction)?),
+        ["layout", name] => Command::ApplyLayout(name.to_string()),
+        ["mode", name] => Command::EnterMode(name.to_string()),
+        ["script", name] => Command::Script(name.to_string()),
+        [name, ..] => {
+            return Err(match USAGE.iter().find(|(n, _)| n == name) {
+                Some((_, usage)) => format!("expected '{}'", usage),
+                None => format!("unknown command '{}'", name),
+            })
+        }
+        // empty commands get skipped before
+        [] => unreachable!(),
+    })
+}
+
+/// Parses one or more commands separated by `;`
+pub fn parse(text: &str) -> Result<CommandList, String> {
+    let mut commands = Vec::new();
+
+    for part in split_commands(text)? {
+        let part = part.trim();
+
+        if part.is_empty() {
+            continue;
+        }
+
+        commands.push(parse_command(part).map_err(|e| format!("{} in '{}'", e, part))?);
+    }
+
+    if commands.is_empty() {
+        return Err("no command given".into());
+    }
+
+    Ok(CommandList(commands))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch(text: &str) -> LaunchSpec {
+        match parse(text).unwrap().0.remove(0) {
+            Command::Launch(spec) => spec,
+            command => panic!("expected launch, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn semicolons_separate_commands() {
+        assert_eq!(
+            parse("close; workspace 2").unwrap().0,
+            vec![Command::CloseTile, Command::ChangeWorkspace(2)]
+        );
+    }
+
+    #[test]
+    fn empty_commands_get_skipped() {
+        assert_eq!(parse(";; quit ;").unwrap().0, vec![Command::Quit]);
+        assert_eq!(parse(" ; ").unwrap_err(), "no command given");
+    }
+
+    #[test]
+    fn quotes_group_words_and_protect_semicolons() {
+        assert_eq!(
+            parse("layout 'two; columns'").unwrap().0,
+            vec![Command::ApplyLayout("two; columns".to_string())]
+        );
+        assert_eq!(
+            parse("mode \"resize\"").unwrap().0,
+            vec![Command::EnterMode("resize".to_string())]
+        );
+        assert_eq!(parse("layout 'two").unwrap_err(), "missing closing '");
+    }
+
+    #[test]
+    fn move_to_workspace_follows_unless_told_otherwise() {
+        assert_eq!(
+            parse("move to workspace 3").unwrap().0,
+            vec![Command::MoveToWorkspace {
+                id: 3,
+                follow: true
+            }]
+        );
+        assert_eq!(
+            parse("move to workspace 3 --no-follow").unwrap().0,
+            vec![Command::MoveToWorkspace {
+                id: 3,
+                follow: false
+            }]
+        );
+    }
+
+    #[test]
+    fn workspace_ids_have_to_exist() {
+        assert_eq!(
+            parse("workspace 11").unwrap_err(),
+            "workspace 11 doesn't exist, ids go from 1 to 10 in 'workspace 11'"
+        );
+        assert_eq!(
+            parse("move to workspace 0").unwrap_err(),
+            "workspace 0 doesn't exist, ids go from 1 to 10 in 'move to workspace 0'"
+        );
+        assert_eq!(
+            parse("workspace two").unwrap_err(),
+            "'workspace' expects a number, got 'two' in 'workspace two'"
+        );
+    }
+
+    #[test]
+    fn unknown_commands_name_the_token() {
+        assert_eq!(
+            parse("close; jump left").unwrap_err(),
+            "unknown command 'jump' in 'jump left'"
+        );
+    }
+
+    #[test]
+    fn known_commands_with_wrong_arguments_show_their_usage() {
+        assert_eq!(
+            parse("focus").unwrap_err(),
+            "expected 'focus left | right | up | down' in 'focus'"
+        );
+    }
+
+    #[test]
+    fn launch_reads_its_options() {
+        assert_eq!(
+            launch("launch --cwd 'C:\\Program Files' --env A=1 --env B=x=y --shell --workspace 4 start notepad"),
+            LaunchSpec {
+                cmd: "start notepad".to_string(),
+                cwd: Some("C:\\Program Files".to_string()),
+                env: vec![
+                    ("A".to_string(), "1".to_string()),
+                    ("B".to_string(), "x=y".to_string())
+                ],
+                shell: true,
+                workspace: Some(4),
+                ..LaunchSpec::default()
+            }
+        );
+    }
+
+    #[test]
+    fn launch_keeps_the_quoting_of_the_program() {
+        assert_eq!(
+            launch("launch \"C:\\My Apps\\app.exe\" --flag").cmd,
+            "\"C:\\My Apps\\app.exe\" --flag"
+        );
+    }
+
+    #[test]
+    fn quoted_launch_is_still_launch() {
+        assert_eq!(launch("'launch' notepad").cmd, "notepad");
+    }
+
+    #[test]
+    fn launch_options_need_values() {
+        assert_eq!(
+            parse("launch --cwd").unwrap_err(),
+            "'launch --cwd' expects a value in 'launch --cwd'"
+        );
+        assert_eq!(
+            parse("launch --env A notepad").unwrap_err(),
+            "'launch --env' expects <name>=<value>, got 'A' in 'launch --env A notepad'"
+        );
+        assert_eq!(
+            parse("launch --workspace 12 notepad").unwrap_err(),
+            "workspace 12 doesn't exist, ids go from 1 to 10 in 'launch --workspace 12 notepad'"
+        );
+        assert_eq!(
+            parse("launch --shell").unwrap_err(),
+            "'launch' expects a program in 'launch --shell'"
+        );
+    }
+}
@@ -1,28 +1,187 @@
-use crate::hot_key_manager::{key::Key, modifier::Modifier, Direction, Keybinding, KeybindingType};
+use crate::command::{Command, CommandList};
+use crate::display::Display;
+use crate::hot_key_manager::{
+    key::{Chord, Key, KeySpec},
+    modifier::Modifier,
+    Keybinding, Registration,
+};
+use crate::launcher::LaunchSpec;
 use crate::tile_grid::SplitDirection;
-use log::debug;
+use lazy_static::lazy_static;
+use log::{debug, warn};
 use regex::Regex;
-use std::io::{Error, ErrorKind, Write};
+use schema::ConfigFile;
+use source::{with_suggestion, Diagnostic};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Mutex;
+use winapi::shared::windef::RECT;
 use winapi::um::wingdi::GetBValue;
 use winapi::um::wingdi::GetGValue;
 use winapi::um::wingdi::GetRValue;
 use winapi::um::wingdi::RGB;
+use winapi::um::winuser::GetSystemMetrics;
+use winapi::um::winuser::SM_CXFRAME;
+use winapi::um::winuser::SM_CYFRAME;
 
 #[macro_use]
 mod macros;
 
+mod convert;
+pub mod diff;
 pub mod hot_reloading;
+mod include;
+mod schema;
+pub mod source;
+mod vars;
+
+lazy_static! {
+    static ref PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+#[derive(Clone, Copy, EnumString, PartialEq, Debug)]
+pub enum FramePolicy {
+    /// Leave the window styles untouched
+    Keep,
+    /// Only remove the caption, the resize frame stays
+    RemoveCaption,
+    /// Remove both the caption and the resize frame
+    RemoveAll,
+}
+
+/// Offsets in pixels that get applied to the calculated window rect.
+/// Positive values shrink the window on that side, negative values grow it.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Insets {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Insets {
+    /// Grows the window by `factor` times the resize border on the left, right and bottom side.
+    /// Some applications (e.g. chromium and firefox) draw their own invisible resize border which
+    /// has to be cancelled out this way.
+    pub fn from_frame_factor(factor: f32) -> Self {
+        let (border_width, border_height) =
+            unsafe { (GetSystemMetrics(SM_CXFRAME), GetSystemMetrics(SM_CYFRAME)) };
+
+        let horizontal = -(border_width as f32 * factor) as i32;
+
+        Self {
+            left: horizontal,
+            top: 0,
+            right: horizontal,
+            bottom: -(border_height as f32 * factor) as i32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, EnumString, PartialEq, Debug)]
+pub enum GeometryPreset {
+    Center,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Full,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct FloatRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Geometry {
+    /// Position relative to the top left corner of the display
+    Rect(FloatRect),
+    Preset(GeometryPreset),
+}
+
+impl Geometry {
+    /// Calculates where a floating window has to be placed on the given display.
+    /// `current` is the rect the window currently has and is used by presets that keep the size of the window.
+    pub fn calculate(&self, display: &Display, current: RECT) -> RECT {
+        let (x, y, width, height) = match self {
+            Geometry::Rect(rect) => (
+                display.left + rect.x,
+                display.top + rect.y,
+                rect.width,
+                rect.height,
+            ),
+            Geometry::Preset(preset) => {
+                let half_width = display.width() / 2;
+                let half_height = display.height() / 2;
+
+                match preset {
+                    GeometryPreset::Center => {
+                        let width = std::cmp::min(current.right - current.left, display.width());
+                        let height = std::cmp::min(current.bottom - current.top, display.height());
+
+                        (
+                            display.left + (display.width() - width) / 2,
+                            display.top + (display.height() - height) / 2,
+                            width,
+                            height,
+                        )
+                    }
+                    GeometryPreset::Left => {
+                        (display.left, display.top, half_width, display.height())
+                    }
+                    GeometryPreset::Right => (
+                        display.left + half_width,
+                        display.top,
+                        half_width,
+                        display.height(),
+                    ),
+                    GeometryPreset::Top => {
+                        (display.left, display.top, display.width(), half_height)
+                    }
+                    GeometryPreset::Bottom => (
+                        display.left,
+                        display.top + half_height,
+                        display.width(),
+                        half_height,
+                    ),
+                    GeometryPreset::Full => {
+                        (display.left, display.top, display.width(), display.height())
+                    }
+                }
+            }
+        };
+
+        RECT {
+            left: x,
+            top: y,
+            right: x + width,
+            bottom: y + height,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Rule {
     pub pattern: Regex,
     pub has_custom_titlebar: bool,
     pub manage: bool,
-    pub chromium: bool,
-    pub firefox: bool,
     pub remove_frame: bool,
+    pub frame: Option<FramePolicy>,
+    pub insets: Insets,
     pub workspace: i32,
+    pub monitor: i32,
+    pub float: bool,
+    pub geometry: Option<Geometry>,
+    pub always_on_top: bool,
+    pub opacity: Option<f32>,
+    pub fullscreen: bool,
+    pub focus: bool,
 }
 
 impl Default for Rule {
@@ -32,14 +191,97 @@ impl Default for Rule {
             has_custom_titlebar: false,
             manage: true,
             remove_frame: true,
-            chromium: false,
-            firefox: false,
+            frame: None,
+            insets: Insets::default(),
             workspace: -1,
+            monitor: -1,
+            float: false,
+            geometry: None,
+            always_on_top: false,
+            opacity: None,
+            fullscreen: false,
+            focus: true,
+        }
+    }
+}
+
+/// Regexes can't be compared, so the patterns get compared by their source
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern.as_str() == other.pattern.as_str()
+            && self.has_custom_titlebar == other.has_custom_titlebar
+            && self.manage == other.manage
+            && self.remove_frame == other.remove_frame
+            && self.frame == other.frame
+            && self.insets == other.insets
+            && self.workspace == other.workspace
+            && self.monitor == other.monitor
+            && self.float == other.float
+            && self.geometry == other.geometry
+            && self.always_on_top == other.always_on_top
+            && self.opacity == other.opacity
+            && self.fullscreen == other.fullscreen
+            && self.focus == other.focus
+    }
+}
+
+impl Rule {
+    /// Returns the frame policy that applies to the matched window.
+    ///
+    /// An explicit `frame` always wins. Otherwise the window keeps its frame if either the rule
+    /// sets `remove_frame` to false or the global `remove_title_bar` setting is disabled.
+    pub fn frame_policy(&self, remove_title_bar: bool) -> FramePolicy {
+        match self.frame {
+            Some(policy) => policy,
+            None if self.remove_frame && remove_title_bar => FramePolicy::RemoveAll,
+            None => FramePolicy::Keep,
         }
     }
 }
 
+/// A placeholder of a layout that gets filled by the first new window matching its criteria.
+#[derive(Debug, Clone, Default)]
+pub struct Slot {
+    pub title: Option<Regex>,
+    pub class: Option<Regex>,
+    pub process: Option<Regex>,
+    /// Command that gets launched when the layout is applied
+    pub cmd: Option<String>,
+}
+
+impl Slot {
+    pub fn matches(&self, title: &str, class: &str, process: &str) -> bool {
+        self.title.as_ref().map_or(true, |r| r.is_match(title))
+            && self.class.as_ref().map_or(true, |r| r.is_match(class))
+            && self.process.as_ref().map_or(true, |r| r.is_match(process))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LayoutNode {
+    Slot(Slot),
+    Split(SplitDirection, Vec<LayoutNode>),
+}
+
 #[derive(Debug, Clone)]
+pub struct Layout {
+    pub name: String,
+    /// The workspace the layout gets applied to. -1 means the current one.
+    pub workspace: i32,
+    pub root: LayoutNode,
+}
+
+/// The name of the mode that uses the top level keybindings
+pub const DEFAULT_MODE: &str = "default";
+
+/// A named set of keybindings that replaces the default keybindings while it is active
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mode {
+    pub name: String,
+    pub keybindings: Vec<Keybinding>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct WorkspaceSetting {
     pub id: i32,
     pub monitor: i32,
@@ -54,6 +296,41 @@ impl Default for WorkspaceSetting {
     }
 }
 
+/// Command lines that get run when something happens. `{name}` gets replaced by the data of the event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hooks {
+    pub on_workspace_change: Option<String>,
+    pub on_window_managed: Option<String>,
+    pub on_focus_change: Option<String>,
+    pub on_config_reload: Option<String>,
+    pub on_work_mode_toggle: Option<String>,
+    /// Milliseconds a hook may run before it gets killed
+    pub timeout: i32,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            on_workspace_change: None,
+            on_window_managed: None,
+            on_focus_change: None,
+            on_config_reload: None,
+            on_work_mode_toggle: None,
+            timeout: 10000,
+        }
+    }
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.on_workspace_change.is_none()
+            && self.on_window_managed.is_none()
+            && self.on_focus_change.is_none()
+            && self.on_config_reload.is_none()
+            && self.on_work_mode_toggle.is_none()
+    }
+}
+
 #[derive(Clone)]
 pub struct Config {
     pub app_bar_height: i32,
@@ -69,9 +346,21 @@ pub struct Config {
     pub remove_title_bar: bool,
     pub remove_task_bar: bool,
     pub display_app_bar: bool,
+    pub manage_dialogs: bool,
+    pub restore_session: bool,
+    pub sequence_timeout: i32,
+    pub show_sequence_hint: bool,
+    pub excluded_classes: Vec<String>,
     pub workspace_settings: Vec<WorkspaceSetting>,
     pub keybindings: Vec<Keybinding>,
     pub rules: Vec<Rule>,
+    pub layouts: Vec<Layout>,
+    pub modes: Vec<Mode>,
+    pub hooks: Hooks,
+    /// Programs that get started once when wwm starts
+    pub autostart: Vec<LaunchSpec>,
+    /// The files the config got loaded from, they get watched for changes
+    pub files: Vec<PathBuf>,
 }
 
 impl Default for Config {
@@ -90,9 +379,19 @@ impl Default for Config {
             multi_monitor: false,
             remove_task_bar: false,
             display_app_bar: false,
+            manage_dialogs: false,
+            restore_session: true,
+            sequence_timeout: 1000,
+            show_sequence_hint: true,
+            excluded_classes: Vec::new(),
             workspace_settings: Vec::new(),
             keybindings: Vec::new(),
             rules: Vec::new(),
+            layouts: Vec::new(),
+            modes: Vec::new(),
+            hooks: Hooks::default(),
+            autostart: Vec::new(),
+            files: Vec::new(),
         }
     }
 }
@@ -104,198 +403,253 @@ impl Config {
     }
 }
 
-pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
-    let mut pathbuf = match dirs::config_dir() {
-        Some(path) => path,
-        None => std::path::PathBuf::new(),
+fn parse_modifier(name: &str) -> Option<Modifier> {
+    match name {
+        "Alt" => Some(Modifier::ALT),
+        "Control" => Some(Modifier::CONTROL),
+        "Shift" => Some(Modifier::SHIFT),
+        "Win" => Some(Modifier::WIN),
+        _ => None,
+    }
+}
+
+fn parse_chord(key_combo: &str) -> Result<Chord, Box<dyn std::error::Error>> {
+    let mut modifier = Modifier::default();
+    let mut rest = key_combo.trim();
+
+    // the key itself can be a '+', so only known modifiers get split off
+    while let Some(idx) = rest.find('+') {
+        match parse_modifier(rest[..idx].trim()) {
+            Some(m) => {
+                modifier.insert(m);
+                rest = rest[idx + 1..].trim_start();
+            }
+            None => break,
+        }
+    }
+
+    if rest.is_empty() {
+        return Err(format!("'{}' is missing a key", key_combo).into());
+    }
+
+    let key = if let Some(scan_code) = rest.strip_prefix("sc:") {
+        let digits = scan_code.trim();
+        let digits = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+            .unwrap_or(digits);
+
+        KeySpec::ScanCode(
+            u32::from_str_radix(digits, 16)
+                .map_err(|_| format!("invalid scan code '{}' in '{}'", scan_code, key_combo))?,
+        )
+    } else if let Some(character) = rest.strip_prefix("char:") {
+        let mut chars = character.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => KeySpec::Character(c),
+            _ => {
+                return Err(format!(
+                    "'char:' has to be followed by exactly one character in '{}'",
+                    key_combo
+                )
+                .into())
+            }
+        }
+    } else if let Some(idx) = rest.find('+') {
+        return Err(format!(
+            "unknown modifier '{}' in '{}'",
+            rest[..idx].trim(),
+            key_combo
+        )
+        .into());
+    } else {
+        KeySpec::Virtual(
+            Key::from_str(rest)
+                .map_err(|_| format!("unknown key '{}' in '{}'", rest, key_combo))?,
+        )
     };
 
-    pathbuf.push("wwm");
+    Ok(Chord { modifier, key })
+}
 
-    if !pathbuf.exists() {
-        debug!("wwm folder doesn't exist yet. Creating the folder");
-        std::fs::create_dir(pathbuf.clone())?;
+/// Loads the file together with its includes and validates the config. Returns the config and the warnings.
+pub fn parse(file: &Path) -> Result<(Config, Vec<Diagnostic>), Diagnostic> {
+    let (mut value, source) = include::load(file)?;
+    let mut unknown = Vec::new();
+
+    vars::apply(&mut value, &source)?;
+
+    let config_file: ConfigFile =
+        serde_path_to_error::deserialize(serde_ignored::Deserializer::new(value, &mut |path| {
+            unknown.push(source::format_path(&path))
+        }))
+        .map_err(|e| {
+            // the path of the root is a dot
+            let path = e.path().to_string();
+
+            source.at(path.trim_start_matches('.'), e.inner().to_string())
+        })?;
+
+    let (mut config, mut warnings) = convert::convert(config_file, &source)?;
+
+    for path in unknown.iter().rev() {
+        let (parent, key) = match path.rfind('.') {
+            Some(idx) => (&path[..idx], &path[idx + 1..]),
+            None => ("", path.as_str()),
+        };
+
+        warnings.insert(
+            0,
+            source.at(
+                path,
+                with_suggestion(
+                    "unknown setting, it gets ignored".into(),
+                    key,
+                    schema::known_keys(parent),
+                ),
+            ),
+        );
     }
 
-    pathbuf.push("config.yaml");
+    config.files = source.files();
+
+    Ok((config, warnings))
+}
+
+/// Uses the file instead of the one in the config directory, e.g. because of `--config`
+pub fn set_path(path: PathBuf) {
+    *PATH.lock().unwrap() = Some(path);
+}
+
+fn get_default_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_default();
+
+    path.push("wwm");
+    path.push("config.yaml");
+    path
+}
+
+/// The config file. `--config` wins over the `WWM_CONFIG` environment variable, which wins over
+/// the config directory.
+pub fn get_path() -> PathBuf {
+    let path = PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .or_else(|| {
+            std::env::var_os("WWM_CONFIG")
+                .filter(|x| !x.is_empty())
+                .map(PathBuf::from)
+        })
+        .unwrap_or_else(get_default_path);
+
+    // the directory of the file gets watched, which needs an absolute path
+    match std::env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path,
+    }
+}
+
+pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+    let path = get_path();
+
+    // only the default config gets created, another path is most likely a typo
+    if path == get_default_path() && !path.exists() {
+        if let Some(dir) = path.parent().filter(|dir| !dir.exists()) {
+            debug!("wwm folder doesn't exist yet. Creating the folder");
+            std::fs::create_dir(dir)?;
+        }
 
-    if !pathbuf.exists() {
         debug!("config file doesn't exist yet. Creating the file");
-        if let Ok(mut file) = std::fs::File::create(pathbuf.clone()) {
+        if let Ok(mut file) = std::fs::File::create(&path) {
             debug!("Initializing config with default values");
             file.write_all(include_bytes!("../default_config.yaml"))?;
         }
     }
 
-    let path = match pathbuf.to_str() {
-        Some(string) => string,
-        None => "",
-    };
-
-    let file_content = std::fs::read_to_string(path)?;
+    let (config, warnings) = parse(&path)?;
 
-    let vec_yaml = yaml_rust::YamlLoader::load_from_str(&file_content)?;
-    let yaml = if !vec_yaml.is_empty() {
-        &vec_yaml[0]
-    } else {
-        &yaml_rust::Yaml::Null
-    };
+    for warning in warnings {
+        warn!("{}", warning);
+    }
 
-    let mut config = Config::new();
-
-    if let yaml_rust::yaml::Yaml::Hash(hash) = yaml {
-        for entry in hash.iter() {
-            let (key, value) = entry;
-            let config_key = key.as_str().ok_or("Invalid config key")?;
-
-            if_str!(config, config_key, value, app_bar_font);
-            if_i32!(config, config_key, value, app_bar_bg);
-            if_i32!(config, config_key, value, app_bar_font_size);
-            if_i32!(config, config_key, value, app_bar_height);
-            if_i32!(config, config_key, value, margin);
-            if_i32!(config, config_key, value, padding);
-            if_bool!(config, config_key, value, light_theme);
-            if_bool!(config, config_key, value, launch_on_startup);
-            if_bool!(config, config_key, value, work_mode);
-            if_bool!(config, config_key, value, multi_monitor);
-            if_bool!(config, config_key, value, remove_title_bar);
-            if_bool!(config, config_key, value, remove_task_bar);
-            if_bool!(config, config_key, value, display_app_bar);
-
-            if config_key == "workspaces" {
-                let workspaces = value.as_vec().ok_or("workspaces has to be an array")?;
-
-                for yaml_workspace in workspaces {
-                    if let yaml_rust::Yaml::Hash(hash) = yaml_workspace {
-                        let mut workspace = WorkspaceSetting::default();
-
-                        for entry in hash.iter() {
-                            let (key, value) = entry;
-                            let hash_key = key.as_str().ok_or("Invalid config key")?;
-
-                            if_i32!(workspace, hash_key, value, id);
-                            if_i32!(workspace, hash_key, value, monitor);
-                        }
-
-                        config.workspace_settings.push(workspace);
-                    }
-                }
-            }
+    Ok(config)
+}
 
-            if config_key == "rules" {
-                let rules = value.as_vec().ok_or("rules has to be an array")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                for yaml_rule in rules {
-                    if let yaml_rust::Yaml::Hash(hash) = yaml_rule {
-                        let mut rule = Rule::default();
+    /// Writes the files into a directory of their own and parses the first one
+    fn parse_files(
+        name: &str,
+        files: &[(&str, &str)],
+    ) -> Result<(Config, Vec<Diagnostic>), Diagnostic> {
+        let dir = std::env::temp_dir().join(format!("wwm-config-{}-{}", std::process::id(), name));
 
-                        for entry in hash.iter() {
-                            let (key, value) = entry;
-                            let hash_key = key.as_str().ok_or("Invalid config key")?;
+        std::fs::create_dir_all(&dir).unwrap();
 
-                            if_regex!(rule, hash_key, value, pattern);
-                            if_bool!(rule, hash_key, value, has_custom_titlebar);
-                            if_bool!(rule, hash_key, value, remove_frame);
-                            if_bool!(rule, hash_key, value, manage);
-                            if_bool!(rule, hash_key, value, chromium);
-                            if_bool!(rule, hash_key, value, firefox);
-                            if_i32!(rule, hash_key, value, workspace);
-                        }
+        for (file, content) in files {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
 
-                        config.rules.push(rule);
-                    }
-                }
-            }
+        parse(&dir.join(files[0].0))
+    }
 
-            if config_key == "keybindings" {
-                let bindings = value.as_vec().ok_or("keybindings has to be an array")?;
-
-                for binding in bindings {
-                    let typ_str = ensure_str!("keybinding", binding, type);
-                    let key_combo = ensure_str!("keybinding", binding, key);
-                    let key_combo_parts = key_combo.split('+').collect::<Vec<&str>>();
-                    let modifier_count = key_combo_parts.len() - 1;
-
-                    let modifier = key_combo_parts
-                        .iter()
-                        .take(modifier_count)
-                        .map(|x| match *x {
-                            "Alt" => Modifier::ALT,
-                            "Control" => Modifier::CONTROL,
-                            "Shift" => Modifier::SHIFT,
-                            _ => Modifier::default(),
-                        })
-                        .fold(Modifier::default(), |mut sum, crr| {
-                            sum.insert(crr);
-
-                            sum
-                        });
-
-                    let key = key_combo_parts
-                        .iter()
-                        .last()
-                        .and_then(|x| Key::from_str(x).ok())
-                        .ok_or("Invalid key")?;
-
-                    let typ =
-                        match typ_str {
-                            "Launch" => KeybindingType::Launch(
-                                ensure_str!("keybinding of type Launch", binding, cmd).to_string(),
-                            ),
-                            "CloseTile" => KeybindingType::CloseTile,
-                            "Quit" => KeybindingType::Quit,
-                            "ChangeWorkspace" => KeybindingType::ChangeWorkspace(ensure_i32!(
-                                "keybinding of type ChangeWorkspace",
-                                binding,
-                                id
-                            )),
-                            "MoveToWorkspace" => KeybindingType::MoveToWorkspace(ensure_i32!(
-                                "keybinding of type MoveToWorkspace",
-                                binding,
-                                id
-                            )),
-                            "MoveWorkspaceToMonitor" => {
-                                KeybindingType::MoveWorkspaceToMonitor(ensure_i32!(
-                                    "keybinding of type MoveWorkspaceToMonitor",
-                                    binding,
-                                    monitor
-                                ))
-                            }
-                            "ToggleFloatingMode" => KeybindingType::ToggleFloatingMode,
-                            "ToggleFullscreen" => KeybindingType::ToggleFullscreen,
-                            "ToggleWorkMode" => KeybindingType::ToggleWorkMode,
-                            "Focus" => KeybindingType::Focus(Direction::from_str(ensure_str!(
-                                "keybinding of type Focus",
-                                binding,
-                                direction
-                            ))?),
-                            "Swap" => KeybindingType::Swap(Direction::from_str(ensure_str!(
-                                "keybinding of type Swap",
-                                binding,
-                                direction
-                            ))?),
-                            "Split" => KeybindingType::Split(SplitDirection::from_str(
-                                ensure_str!("keybinding of type Split", binding, direction),
-                            )?),
-                            x => {
-                                return Err(Box::new(Error::new(
-                                    ErrorKind::InvalidInput,
-                                    "unknown type ".to_string() + x,
-                                )))
-                            }
-                        };
-
-                    config.keybindings.push(Keybinding {
-                        key,
-                        modifier,
-                        typ,
-                        registered: false,
-                    });
-                }
-            }
+    fn parse_error(name: &str, files: &[(&str, &str)]) -> Diagnostic {
+        match parse_files(name, files) {
+            Ok(_) => panic!("expected the config to be invalid"),
+            Err(diagnostic) => diagnostic,
         }
-        //Convert normal hexadecimal color format to winapi hexadecimal color format
-        convert_color_format!(config.app_bar_bg);
     }
-    Ok(config)
+
+    #[test]
+    fn type_errors_point_at_the_setting() {
+        let config = "rules:\n  - pattern: a\n  - pattern: b\n    workspace: two\n";
+        let diagnostic = parse_error("type_error", &[("config.yaml", config)]);
+
+        assert_eq!(diagnostic.location, Some((4, 5)));
+        assert_eq!(
+            diagnostic.short(),
+            "config.yaml:4:5: rules[1].workspace: invalid type: string \"two\", expected i32"
+        );
+    }
+
+    #[test]
+    fn unknown_settings_get_a_suggestion() {
+        let config = "rules:\n  - pattern: a\n    maange: false\n";
+        let (_, warnings) = parse_files("unknown_setting", &[("config.yaml", config)]).unwrap();
+
+        assert_eq!(
+            warnings.iter().map(Diagnostic::short).collect::<Vec<String>>(),
+            vec![
+                "config.yaml:3:5: rules[0].maange: unknown setting, it gets ignored, did you mean 'manage'?"
+            ]
+        );
+    }
+
+    #[test]
+    fn workspace_ids_have_to_exist() {
+        let config = "rules:\n  - pattern: a\n    workspace: 11\n";
+        let diagnostic = parse_error("workspace_id", &[("config.yaml", config)]);
+
+        assert_eq!(
+            diagnostic.short(),
+            "config.yaml:3:5: rules[0].workspace: workspace 11 doesn't exist, ids go from 1 to 10"
+        );
+    }
+
+    #[test]
+    fn errors_in_included_files_point_at_that_file() {
+        let config = "include:\n  - rules.yaml\nrules:\n  - pattern: c\n";
+        let rules = "rules:\n  - pattern: a\n  - pattern: b\n    workspace: 0\n";
+        let diagnostic = parse_error("include", &[("config.yaml", config), ("rules.yaml", rules)]);
+
+        assert_eq!(diagnostic.file.file_name().unwrap(), "rules.yaml");
+        assert_eq!(
+            diagnostic.short(),
+            "rules.yaml:4:5: rules[1].workspace: workspace 0 doesn't exist, ids go from 1 to 10"
+        );
+    }
 }
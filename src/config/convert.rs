@@ -0,0 +1,564 @@
+use super::schema::*;
+use super::source::{with_suggestion, Diagnostic, Source};
+use super::*;
+use crate::command::parser::validate_workspace_id;
+use winapi::um::winuser::SM_CMONITORS;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Turns the file into a config and checks everything the structure alone can't tell
+struct Converter<'a> {
+    source: &'a Source,
+    /// The number of displays wwm uses, which is only the primary one without multi_monitor
+    monitors: i32,
+    multi_monitor: bool,
+    modes: Vec<String>,
+    layouts: Vec<String>,
+    warnings: Vec<Diagnostic>,
+}
+
+fn path_of(parent: &str, key: &str) -> String {
+    match parent.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", parent, key),
+    }
+}
+
+impl<'a> Converter<'a> {
+    fn error<T>(&self, path: &str, message: impl Into<String>) -> Result<T> {
+        Err(self.source.at(path, message))
+    }
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        let warning = self.source.at(path, message);
+        self.warnings.push(warning);
+    }
+    fn workspace(&self, path: &str, id: i32) -> Result<i32> {
+        validate_workspace_id(id).map_err(|e| self.source.at(path, e))
+    }
+    fn missing_monitor(&self, monitor: i32) -> String {
+        match self.multi_monitor {
+            true => format!(
+                "monitor {} doesn't exist, there are {} monitor(s)",
+                monitor, self.monitors
+            ),
+            false => format!(
+                "monitor {} isn't used, because multi_monitor is turned off",
+                monitor
+            ),
+        }
+    }
+    /// Monitors come and go, so a missing one only gets reported. Returns -1 for a missing one.
+    fn monitor(&mut self, path: &str, monitor: i32) -> i32 {
+        if (1..=self.monitors).contains(&monitor) {
+            return monitor;
+        }
+
+        let message = format!(
+            "{}. The setting gets ignored",
+            self.missing_monitor(monitor)
+        );
+        self.warn(path, message);
+
+        -1
+    }
+    fn regex(&self, path: &str, pattern: &str) -> Result<Regex> {
+        Regex::new(pattern).map_err(|e| self.source.at(path, e.to_string()))
+    }
+    fn name<T: FromStr>(&self, path: &str, value: &str, names: &[&str]) -> Result<T> {
+        T::from_str(value).or_else(|_| {
+            self.error(
+                path,
+                with_suggestion(
+                    format!("'{}' has to be one of {}", value, names.join(", ")),
+                    value,
+                    names,
+                ),
+            )
+        })
+    }
+
+    fn rule(&mut self, path: &str, file: RuleFile) -> Result<Rule> {
+        let mut rule = Rule::default();
+
+        if let Some(pattern) = &file.pattern {
+            rule.pattern = self.regex(&path_of(path, "pattern"), pattern)?;
+        }
+
+        // chromium and firefox are presets for the frame settings, so they can't be combined with
+        // the settings they set
+        if file.chromium && file.firefox {
+            return self.error(
+                &path_of(path, "chromium"),
+                "can't be combined with 'firefox', only one of the presets can be used",
+            );
+        }
+
+        if file.chromium || file.firefox {
+            let preset = if file.chromium { "chromium" } else { "firefox" };
+
+            for (setting, given) in [
+                ("frame", file.frame.is_some()),
+                ("insets", file.insets.is_some()),
+            ] {
+                if given {
+                    return self.error(
+                        &path_of(path, setting),
+                        format!("can't be combined with '{}', which sets it itself", preset),
+                    );
+                }
+            }
+
+            let factor = if file.chromium { 2.0 } else { 1.5 };
+
+            rule.frame = Some(FramePolicy::Keep);
+            rule.has_custom_titlebar = true;
+            rule.insets = Insets::from_frame_factor(factor);
+        }
+
+        set_given!(
+            rule,
+            file,
+            has_custom_titlebar,
+            manage,
+            remove_frame,
+            float,
+            always_on_top,
+            fullscreen,
+            focus
+        );
+
+        if let Some(frame) = &file.frame {
+            rule.frame = Some(self.name(
+                &path_of(path, "frame"),
+                frame,
+                &["Keep", "RemoveCaption", "RemoveAll"],
+            )?);
+        }
+
+        if let Some(insets) = file.insets {
+            rule.insets = Insets {
+                left: insets.left,
+                top: insets.top,
+                right: insets.right,
+                bottom: insets.bottom,
+            };
+        }
+
+        if let Some(id) = file.workspace {
+            rule.workspace = self.workspace(&path_of(path, "workspace"), id)?;
+        }
+
+        if let Some(monitor) = file.monitor {
+            rule.monitor = self.monitor(&path_of(path, "monitor"), monitor);
+        }
+
+        rule.geometry = match file.geometry {
+            Some(StringOr::String(preset)) => Some(Geometry::Preset(self.name(
+                &path_of(path, "geometry"),
+                &preset,
+                &["Center", "Left", "Right", "Top", "Bottom", "Full"],
+            )?)),
+            Some(StringOr::Hash(rect)) => Some(Geometry::Rect(FloatRect {
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+            })),
+            None => None,
+        };
+
+        if let Some(opacity) = file.opacity {
+            if !(0.0..=1.0).contains(&opacity) {
+                return self.error(&path_of(path, "opacity"), "has to be between 0 and 1");
+            }
+
+            rule.opacity = Some(opacity);
+        }
+
+        Ok(rule)
+    }
+
+    fn layout_node(&self, path: &str, node: LayoutNodeFile) -> Result<LayoutNode> {
+        if let Some(split) = &node.split {
+            let direction =
+                self.name(&path_of(path, "split"), split, &["Horizontal", "Vertical"])?;
+
+            if node.children.is_empty() {
+                return self.error(path, "a split of a layout needs at least one child");
+            }
+
+            return Ok(LayoutNode::Split(
+                direction,
+                node.children
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, child)| self.layout_node(&format!("{}.children[{}]", path, i), child))
+                    .collect::<Result<Vec<LayoutNode>>>()?,
+            ));
+        }
+
+        let regex = |key: &str, value: &Option<String>| -> Result<Option<Regex>> {
+            value
+                .as_ref()
+                .map(|pattern| self.regex(&path_of(path, key), pattern))
+                .transpose()
+        };
+
+        let slot = Slot {
+            title: regex("title", &node.title)?,
+            class: regex("class", &node.class)?,
+            process: regex("process", &node.process)?,
+            cmd: node.cmd,
+        };
+
+        if slot.title.is_none() && slot.class.is_none() && slot.process.is_none() {
+            return self.error(
+                path,
+                "a slot of a layout needs a title, class or process to match",
+            );
+        }
+
+        Ok(LayoutNode::Slot(slot))
+    }
+
+    fn env(
+        &self,
+        path: &str,
+        env: BTreeMap<String, serde_yaml::Value>,
+    ) -> Result<Vec<(String, String)>> {
+        env.into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_yaml::Value::String(s) => s,
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    _ => {
+                        return self.error(&format!("{}.env.{}", path, name), "has to be a string")
+                    }
+                };
+
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    fn launch_spec(&self, path: &str, file: StringOr<LaunchFile>) -> Result<LaunchSpec> {
+        let file = match file {
+            StringOr::String(cmd) => return Ok(LaunchSpec::from_command_line(&cmd)),
+            StringOr::Hash(file) => file,
+        };
+
+        if file.cmd.is_empty() {
+            return self.error(path, "a program of autostart has to have a 'cmd'");
+        }
+
+        Ok(LaunchSpec {
+            workspace: file
+                .workspace
+                .map(|id| self.workspace(&path_of(path, "workspace"), id))
+                .transpose()?,
+            env: self.env(path, file.env)?,
+            cmd: file.cmd,
+            args: file.args,
+            cwd: file.cwd,
+            shell: file.shell,
+        })
+    }
+
+    /// Translates a keybinding that uses a type with settings instead of a command
+    fn typed_command(&self, path: &str, typ: &str, file: &KeybindingFile) -> Result<Command> {
+        let required = |key: &str, value: &Option<String>| -> Result<String> {
+            value.clone().ok_or_else(|| {
+                self.source.at(
+                    path,
+                    format!("a keybinding of type {} has to have a '{}'", typ, key),
+                )
+            })
+        };
+        let id = |key: &str, value: Option<i32>| -> Result<i32> {
+            value.ok_or_else(|| {
+                self.source.at(
+                    path,
+                    format!("a keybinding of type {} has to have a '{}'", typ, key),
+                )
+            })
+        };
+        let directions = &["Left", "Right", "Up", "Down"];
+
+        Ok(match typ {
+            "Launch" => Command::Launch(LaunchSpec {
+                cmd: required("cmd", &file.cmd)?,
+                args: file.args.clone(),
+                cwd: file.cwd.clone(),
+                env: self.env(path, file.env.clone())?,
+                shell: file.shell,
+                workspace: file
+                    .workspace
+                    .map(|id| self.workspace(&path_of(path, "workspace"), id))
+                    .transpose()?,
+            }),
+            "ApplyLayout" => Command::ApplyLayout(required("name", &file.name)?),
+            "EnterMode" => Command::EnterMode(required("mode", &file.mode)?),
+            "LeaveMode" => Command::EnterMode(DEFAULT_MODE.to_string()),
+            "Script" => Command::Script(required("name", &file.name)?),
+            "CloseTile" => Command::CloseTile,
+            "Undo" => Command::Undo,
+            "Redo" => Command::Redo,
+            "Quit" => Command::Quit,
+            "ReloadConfig" => Command::ReloadConfig,
+            "ChangeWorkspace" => {
+                Command::ChangeWorkspace(self.workspace(&path_of(path, "id"), id("id", file.id)?)?)
+            }
+            "MoveToWorkspace" => Command::MoveToWorkspace {
+                id: self.workspace(&path_of(path, "id"), id("id", file.id)?)?,
+                follow: true,
+            },
+            "MoveWorkspaceToMonitor" => {
+                Command::MoveWorkspaceToMonitor(id("monitor", file.monitor)?)
+            }
+            "ToggleFloatingMode" => Command::ToggleFloatingMode,
+            "ToggleFullscreen" => Command::ToggleFullscreen,
+            "ToggleWorkMode" => Command::ToggleWorkMode,
+            "Focus" => Command::Focus(self.name(
+                &path_of(path, "direction"),
+                &required("direction", &file.direction)?,
+                directions,
+            )?),
+            "Swap" => Command::Swap(self.name(
+                &path_of(path, "direction"),
+                &required("direction", &file.direction)?,
+                directions,
+            )?),
+            "Split" => Command::Split(self.name(
+                &path_of(path, "direction"),
+                &required("direction", &file.direction)?,
+                &["Horizontal", "Vertical"],
+            )?),
+            x => {
+                return self.error(
+                    &path_of(path, "type"),
+                    with_suggestion(format!("unknown type '{}'", x), x, KEYBINDING_TYPES),
+                )
+            }
+        })
+    }
+
+    fn keybinding(&mut self, path: &str, file: KeybindingFile) -> Result<Keybinding> {
+        if file.key.is_empty() {
+            return self.error(path, "a keybinding has to have a 'key'");
+        }
+
+        // split always returns at least one part, so there is always a first chord
+        let chords = file
+            .key
+            .split(',')
+            .map(|x| parse_chord(x.trim()))
+            .collect::<std::result::Result<Vec<Chord>, _>>()
+            .map_err(|e| self.source.at(&path_of(path, "key"), e.to_string()))?;
+
+        let commands = if let Some(text) = &file.command {
+            text.parse::<CommandList>()
+                .map_err(|e| self.source.at(&path_of(path, "command"), e))?
+        } else if let Some(typ) = &file.typ {
+            CommandList(vec![self.typed_command(path, typ, &file)?])
+        } else {
+            return self.error(path, "a keybinding has to have a 'command'");
+        };
+
+        for command in &commands.0 {
+            match command {
+                Command::EnterMode(name) if name != DEFAULT_MODE && !self.modes.contains(name) => {
+                    return self.error(
+                        path,
+                        with_suggestion(
+                            format!("the mode '{}' doesn't exist", name),
+                            name,
+                            &self.modes.iter().map(String::as_str).collect::<Vec<&str>>(),
+                        ),
+                    );
+                }
+                Command::ApplyLayout(name) if !self.layouts.contains(name) => {
+                    return self.error(
+                        path,
+                        with_suggestion(
+                            format!("the layout '{}' doesn't exist", name),
+                            name,
+                            &self
+                                .layouts
+                                .iter()
+                                .map(String::as_str)
+                                .collect::<Vec<&str>>(),
+                        ),
+                    );
+                }
+                Command::MoveWorkspaceToMonitor(monitor)
+                    if !(1..=self.monitors).contains(monitor) =>
+                {
+                    let message = self.missing_monitor(*monitor);
+                    self.warn(path, message);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Keybinding {
+            chords,
+            repeat: file.repeat.unwrap_or(true),
+            commands,
+            resolved: Vec::new(),
+            registration: Registration::Unregistered,
+        })
+    }
+
+    fn keybindings(&mut self, path: &str, files: Vec<KeybindingFile>) -> Result<Vec<Keybinding>> {
+        files
+            .into_iter()
+            .enumerate()
+            .map(|(i, file)| self.keybinding(&format!("{}[{}]", path, i), file))
+            .collect()
+    }
+}
+
+/// The types a keybinding can have instead of a command
+const KEYBINDING_TYPES: &[&str] = &[
+    "Launch",
+    "ApplyLayout",
+    "EnterMode",
+    "LeaveMode",
+    "Script",
+    "CloseTile",
+    "Undo",
+    "Redo",
+    "Quit",
+    "ReloadConfig",
+    "ChangeWorkspace",
+    "MoveToWorkspace",
+    "MoveWorkspaceToMonitor",
+    "ToggleFloatingMode",
+    "ToggleFullscreen",
+    "ToggleWorkMode",
+    "Focus",
+    "Swap",
+    "Split",
+];
+
+/// Fills in the defaults and validates the settings. Returns the config and the warnings.
+pub fn convert(file: ConfigFile, source: &Source) -> Result<(Config, Vec<Diagnostic>)> {
+    let mut converter = Converter {
+        source,
+        monitors: 1,
+        multi_monitor: false,
+        modes: file.modes.iter().map(|m| m.name.clone()).collect(),
+        layouts: file.layouts.iter().map(|l| l.name.clone()).collect(),
+        warnings: Vec::new(),
+    };
+    let mut config = Config::new();
+
+    set_given!(
+        config,
+        file,
+        app_bar_height,
+        app_bar_bg,
+        app_bar_font,
+        app_bar_font_size,
+        work_mode,
+        light_theme,
+        multi_monitor,
+        launch_on_startup,
+        margin,
+        padding,
+        remove_title_bar,
+        remove_task_bar,
+        display_app_bar,
+        manage_dialogs,
+        restore_session,
+        sequence_timeout,
+        show_sequence_hint
+    );
+
+    // the same displays display::init picks
+    converter.multi_monitor = config.multi_monitor;
+
+    if config.multi_monitor {
+        converter.monitors = unsafe { GetSystemMetrics(SM_CMONITORS) };
+    }
+
+    if config.sequence_timeout <= 0 {
+        return converter.error("sequence_timeout", "has to be positive");
+    }
+
+    config.excluded_classes = file.excluded_classes;
+
+    for (i, workspace) in file.workspaces.into_iter().enumerate() {
+        let path = format!("workspaces[{}]", i);
+
+        config.workspace_settings.push(WorkspaceSetting {
+            id: converter.workspace(&path_of(&path, "id"), workspace.id)?,
+            monitor: match workspace.monitor {
+                Some(monitor) => converter.monitor(&path_of(&path, "monitor"), monitor),
+                None => -1,
+            },
+        });
+    }
+
+    for (i, rule) in file.rules.into_iter().enumerate() {
+        let rule = converter.rule(&format!("rules[{}]", i), rule)?;
+        config.rules.push(rule);
+    }
+
+    for (i, layout) in file.layouts.into_iter().enumerate() {
+        let path = format!("layouts[{}]", i);
+
+        config.layouts.push(Layout {
+            name: layout.name,
+            workspace: match layout.workspace {
+                Some(id) => converter.workspace(&path_of(&path, "workspace"), id)?,
+                None => -1,
+            },
+            root: converter.layout_node(&path_of(&path, "root"), layout.root)?,
+        });
+    }
+
+    config.keybindings = converter.keybindings("keybindings", file.keybindings)?;
+
+    for (i, mode) in file.modes.into_iter().enumerate() {
+        let path = format!("modes[{}]", i);
+
+        if mode.name == DEFAULT_MODE {
+            return converter.error(
+                &path_of(&path, "name"),
+                format!("the mode '{}' can't be redefined", mode.name),
+            );
+        }
+
+        config.modes.push(Mode {
+            name: mode.name,
+            keybindings: converter.keybindings(&path_of(&path, "keybindings"), mode.keybindings)?,
+        });
+    }
+
+    if let Some(hooks) = file.hooks {
+        config.hooks = Hooks {
+            on_workspace_change: hooks.on_workspace_change,
+            on_window_managed: hooks.on_window_managed,
+            on_focus_change: hooks.on_focus_change,
+            on_config_reload: hooks.on_config_reload,
+            on_work_mode_toggle: hooks.on_work_mode_toggle,
+            timeout: hooks.timeout.unwrap_or(config.hooks.timeout),
+        };
+
+        if config.hooks.timeout <= 0 {
+            return converter.error("hooks.timeout", "has to be positive");
+        }
+    }
+
+    for (i, program) in file.autostart.into_iter().enumerate() {
+        let spec = converter.launch_spec(&format!("autostart[{}]", i), program)?;
+        config.autostart.push(spec);
+    }
+
+    //Convert normal hexadecimal color format to winapi hexadecimal color format
+    convert_color_format!(config.app_bar_bg);
+
+    Ok((config, converter.warnings))
+}
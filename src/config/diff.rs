@@ -0,0 +1,325 @@
+use super::Config;
+
+/// Something that has to be done to the running state so it matches a new config.
+/// The actions are ordered, e.g. the app bar gets closed before the displays are measured again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigAction {
+    CloseAppBar,
+    /// Measures the displays again, e.g. after the app bar changed its height
+    ReinitDisplays,
+    /// Moves the workspaces to the monitors of their settings
+    PlaceWorkspaces,
+    CreateAppBar,
+    ShowTaskBar,
+    HideTaskBar,
+    /// Matches the tiled windows against the rules again and restyles them. Only the frame,
+    /// insets and opacity change, a window stays where it is even if the new rule has another
+    /// `workspace`, `monitor`, `manage` or `float` setting.
+    RematchRules,
+    /// Applies the frame policies of the managed windows again
+    RestyleWindows,
+    SetLaunchOnStartup(bool),
+    RegisterKeybindings,
+    /// Draws the visible workspaces again
+    Relayout,
+}
+
+/// The settings that differ between the running config and a new one
+pub struct ConfigDiff<'a> {
+    old: &'a Config,
+    new: &'a Config,
+    changed: Vec<&'static str>,
+}
+
+impl<'a> ConfigDiff<'a> {
+    pub fn new(old: &'a Config, new: &'a Config) -> Self {
+        // layouts get looked up whenever they are applied, so there is nothing to compare
+        let changed = changed_settings!(
+            old,
+            new,
+            app_bar_height,
+            app_bar_bg,
+            app_bar_font,
+            app_bar_font_size,
+            work_mode,
+            light_theme,
+            multi_monitor,
+            launch_on_startup,
+            margin,
+            padding,
+            remove_title_bar,
+            remove_task_bar,
+            display_app_bar,
+            manage_dialogs,
+            restore_session,
+            sequence_timeout,
+            show_sequence_hint,
+            excluded_classes,
+            workspace_settings,
+            keybindings,
+            rules,
+            modes,
+            hooks,
+            autostart
+        );
+
+        Self { old, new, changed }
+    }
+    pub fn changed(&self) -> &[&'static str] {
+        &self.changed
+    }
+    fn any_changed(&self, settings: &[&str]) -> bool {
+        self.changed.iter().any(|x| settings.contains(x))
+    }
+    /// What has to be done to apply the new config. Settings that are only read when they are
+    /// needed (e.g. hooks or excluded_classes) don't need an action.
+    pub fn actions(&self) -> Vec<ConfigAction> {
+        let mut actions = Vec::new();
+        let app_bar_style = self.any_changed(&[
+            "app_bar_bg",
+            "app_bar_font",
+            "app_bar_font_size",
+            "app_bar_height",
+            "light_theme",
+        ]);
+        let displays = self.any_changed(&["multi_monitor", "display_app_bar", "app_bar_height"]);
+        // the app bar has a window on every display
+        let recreate_app_bar = app_bar_style || displays;
+
+        if self.old.display_app_bar && (!self.new.display_app_bar || recreate_app_bar) {
+            actions.push(ConfigAction::CloseAppBar);
+        }
+
+        if displays {
+            actions.push(ConfigAction::ReinitDisplays);
+        }
+
+        if displays || self.any_changed(&["workspace_settings"]) {
+            actions.push(ConfigAction::PlaceWorkspaces);
+        }
+
+        if self.new.display_app_bar && (!self.old.display_app_bar || recreate_app_bar) {
+            actions.push(ConfigAction::CreateAppBar);
+        }
+
+        if self.any_changed(&["remove_task_bar"]) {
+            actions.push(match self.new.remove_task_bar {
+                true => ConfigAction::HideTaskBar,
+                false => ConfigAction::ShowTaskBar,
+            });
+        }
+
+        if self.any_changed(&["rules"]) {
+            actions.push(ConfigAction::RematchRules);
+        } else if self.any_changed(&["remove_title_bar"]) {
+            actions.push(ConfigAction::RestyleWindows);
+        }
+
+        if self.any_changed(&["launch_on_startup"]) {
+            actions.push(ConfigAction::SetLaunchOnStartup(self.new.launch_on_startup));
+        }
+
+        if self.any_changed(&[
+            "keybindings",
+            "modes",
+            "sequence_timeout",
+            "show_sequence_hint",
+        ]) {
+            actions.push(ConfigAction::RegisterKeybindings);
+        }
+
+        if displays
+            || self.any_changed(&[
+                "workspace_settings",
+                "rules",
+                "remove_title_bar",
+                "margin",
+                "padding",
+            ])
+        {
+            actions.push(ConfigAction::Relayout);
+        }
+
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Mode, Rule};
+    use crate::hot_key_manager::key::{Chord, Key, KeySpec};
+    use crate::hot_key_manager::modifier::Modifier;
+    use crate::hot_key_manager::{Keybinding, Registration};
+    use regex::Regex;
+    use ConfigAction::*;
+
+    fn actions(old: &Config, change: impl FnOnce(&mut Config)) -> Vec<ConfigAction> {
+        let mut new = old.clone();
+        change(&mut new);
+
+        ConfigDiff::new(old, &new).actions()
+    }
+
+    fn with_app_bar() -> Config {
+        Config {
+            display_app_bar: true,
+            ..Config::default()
+        }
+    }
+
+    fn keybinding(commands: &str) -> Keybinding {
+        Keybinding {
+            commands: commands.parse().unwrap(),
+            chords: vec![Chord {
+                modifier: Modifier::ALT,
+                key: KeySpec::Virtual(Key::Q),
+            }],
+            repeat: false,
+            resolved: Vec::new(),
+            registration: Registration::Unregistered,
+        }
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let config = with_app_bar();
+        let diff = ConfigDiff::new(&config, &config);
+
+        assert!(diff.changed().is_empty());
+        assert!(diff.actions().is_empty());
+    }
+
+    #[test]
+    fn app_bar_height_recreates_the_app_bar_and_measures_the_displays() {
+        let actions = actions(&with_app_bar(), |c| c.app_bar_height = 30);
+
+        assert_eq!(
+            actions,
+            vec![
+                CloseAppBar,
+                ReinitDisplays,
+                PlaceWorkspaces,
+                CreateAppBar,
+                Relayout
+            ]
+        );
+    }
+
+    #[test]
+    fn app_bar_style_recreates_the_app_bar() {
+        assert_eq!(
+            actions(&with_app_bar(), |c| c.app_bar_bg = 0xffffff),
+            vec![CloseAppBar, CreateAppBar]
+        );
+        assert_eq!(
+            actions(&with_app_bar(), |c| c.light_theme = true),
+            vec![CloseAppBar, CreateAppBar]
+        );
+    }
+
+    #[test]
+    fn app_bar_style_without_app_bar_does_nothing() {
+        assert!(actions(&Config::default(), |c| c.app_bar_bg = 0xffffff).is_empty());
+    }
+
+    #[test]
+    fn display_app_bar() {
+        assert_eq!(
+            actions(&Config::default(), |c| c.display_app_bar = true),
+            vec![ReinitDisplays, PlaceWorkspaces, CreateAppBar, Relayout]
+        );
+        assert_eq!(
+            actions(&with_app_bar(), |c| c.display_app_bar = false),
+            vec![CloseAppBar, ReinitDisplays, PlaceWorkspaces, Relayout]
+        );
+    }
+
+    #[test]
+    fn rules_get_matched_again() {
+        let actions = actions(&Config::default(), |c| {
+            c.rules.push(Rule {
+                pattern: Regex::new("firefox").unwrap(),
+                ..Rule::default()
+            })
+        });
+
+        assert_eq!(actions, vec![RematchRules, Relayout]);
+    }
+
+    #[test]
+    fn remove_title_bar_restyles_the_windows() {
+        assert_eq!(
+            actions(&Config::default(), |c| c.remove_title_bar = true),
+            vec![RestyleWindows, Relayout]
+        );
+    }
+
+    #[test]
+    fn rules_and_remove_title_bar_only_match_once() {
+        let actions = actions(&Config::default(), |c| {
+            c.remove_title_bar = true;
+            c.rules.push(Rule::default());
+        });
+
+        assert_eq!(actions, vec![RematchRules, Relayout]);
+    }
+
+    #[test]
+    fn keybindings_and_modes_get_registered_again() {
+        assert_eq!(
+            actions(&Config::default(), |c| c
+                .keybindings
+                .push(keybinding("close"))),
+            vec![RegisterKeybindings]
+        );
+        assert_eq!(
+            actions(&Config::default(), |c| c.modes.push(Mode {
+                name: String::from("resize"),
+                keybindings: vec![keybinding("quit")],
+            })),
+            vec![RegisterKeybindings]
+        );
+        assert_eq!(
+            actions(&Config::default(), |c| c.sequence_timeout = 500),
+            vec![RegisterKeybindings]
+        );
+    }
+
+    #[test]
+    fn runtime_state_of_keybindings_is_no_change() {
+        let mut old = Config::default();
+        old.keybindings.push(keybinding("close"));
+
+        let actions = actions(&old, |c| {
+            c.keybindings[0].registration = Registration::Registered(1)
+        });
+
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn task_bar_and_launch_on_startup() {
+        assert_eq!(
+            actions(&Config::default(), |c| c.remove_task_bar = true),
+            vec![HideTaskBar]
+        );
+        assert_eq!(
+            actions(&Config::default(), |c| c.launch_on_startup = true),
+            vec![SetLaunchOnStartup(true)]
+        );
+    }
+
+    #[test]
+    fn settings_that_are_read_when_needed_have_no_action() {
+        let old = Config::default();
+        let mut new = old.clone();
+        new.manage_dialogs = true;
+        new.excluded_classes.push(String::from("Notepad"));
+
+        let diff = ConfigDiff::new(&old, &new);
+
+        assert_eq!(diff.changed(), &["manage_dialogs", "excluded_classes"]);
+        assert!(diff.actions().is_empty());
+    }
+}
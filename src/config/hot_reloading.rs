@@ -1,42 +1,137 @@
 use crate::event::Event;
 use crate::CHANNEL;
-use log::{debug, error};
+use crate::CONFIG;
+use log::{debug, error, info};
 use notify::watcher;
 use notify::DebouncedEvent;
+use notify::RecommendedWatcher;
 use notify::RecursiveMode;
 use notify::Watcher;
-use std::sync::mpsc::channel;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+
+/// Editors often save in several steps, which should only cause one reload
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often the watched directories get checked, e.g. after the files of the config changed
+const REARM_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Windows paths aren't case sensitive
+fn is_same_path(a: &Path, b: &Path) -> bool {
+    a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+}
+
+/// The files of the config that are affected by the event
+fn changed_files(event: &DebouncedEvent) -> Vec<&PathBuf> {
+    match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Remove(path) => {
+            vec![path]
+        }
+        // editors that save atomically rename a temporary file to the config
+        DebouncedEvent::Rename(from, to) => vec![from, to],
+        _ => Vec::new(),
+    }
+}
+
+/// Watches the directories of the files, not the files themselves. Editors that save by replacing
+/// the file would otherwise remove the watch with the old file.
+fn rearm(watcher: &mut RecommendedWatcher, watched: &mut Vec<PathBuf>, files: &[PathBuf]) {
+    let mut directories: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.parent().map(Path::to_path_buf))
+        .collect();
+
+    directories.sort();
+    directories.dedup();
+
+    for directory in watched.iter().filter(|d| !directories.contains(d)) {
+        let _ = watcher.unwatch(directory);
+    }
+
+    watched.retain(|d| directories.contains(d));
+
+    for directory in directories {
+        if watched.contains(&directory) {
+            continue;
+        }
+
+        // the directory might not exist right now, the next try happens after the interval
+        match watcher.watch(&directory, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                debug!("Watching {}", directory.display());
+                watched.push(directory);
+            }
+            Err(e) => debug!("Failed to watch {}: {:?}", directory.display(), e),
+        }
+    }
+}
 
 pub fn start() {
     std::thread::spawn(|| {
         let (tx, rx) = channel();
 
-        let mut watcher = watcher(tx, std::time::Duration::from_millis(10))
-            .expect("Failed to spawn file watcher");
+        let mut watcher = match watcher(tx, DEBOUNCE) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                error!("Failed to spawn the file watcher of the config {}", e);
+                return;
+            }
+        };
+        let mut watched: Vec<PathBuf> = Vec::new();
 
-        let mut path = dirs::config_dir().expect("Failed to get config dir");
+        loop {
+            let files = CONFIG.lock().unwrap().files.clone();
 
-        path.push("wwm");
-        path.push("config.yaml");
+            rearm(&mut watcher, &mut watched, &files);
 
-        watcher
-            .watch(path, RecursiveMode::NonRecursive)
-            .expect("Failed to watch config directory");
+            let ev = match rx.recv_timeout(REARM_INTERVAL) {
+                Ok(ev) => ev,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    error!("The file watcher of the config stopped");
+                    break;
+                }
+            };
 
-        loop {
-            match rx.recv() {
-                Ok(ev) => match ev {
-                    DebouncedEvent::Write(_) => {
-                        debug!("detected config change");
-                        CHANNEL
-                            .sender
-                            .clone()
-                            .send(Event::ReloadConfig)
-                            .expect("Failed to send ReloadConfig event");
-                    }
-                    _ => {}
-                },
-                Err(e) => error!("watch error: {:?}", e),
+            match &ev {
+                DebouncedEvent::Remove(path) | DebouncedEvent::Rename(path, _)
+                    if watched.contains(path) =>
+                {
+                    // the watch is gone with the directory
+                    let _ = watcher.unwatch(path);
+                    watched.retain(|d| d != path);
+                    continue;
+                }
+                DebouncedEvent::Error(e, path) => {
+                    error!("watch error: {:?} {:?}", e, path);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let is_config_change = match &ev {
+                DebouncedEvent::Rescan => true,
+                ev => changed_files(ev)
+                    .iter()
+                    .any(|changed| files.iter().any(|file| is_same_path(changed, file))),
+            };
+
+            if !is_config_change {
+                continue;
+            }
+
+            // the editor removed the file and didn't write the new one yet
+            if let Some(missing) = files.iter().find(|file| !file.exists()) {
+                info!("Waiting for {} to come back", missing.display());
+                continue;
+            }
+
+            debug!("detected config change");
+            if let Err(e) = CHANNEL.sender.clone().send(Event::ReloadConfig) {
+                error!("Failed to send ReloadConfig event {}", e);
+                return;
             }
         }
     });
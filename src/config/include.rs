@@ -0,0 +1,323 @@
+use super::source::{parse_error, Diagnostic, Source};
+use super::vars;
+use serde_yaml::{Mapping, Value};
+use std::path::{Path, PathBuf};
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Reads the file and the files it includes. The documents are in the order they get merged,
+/// the included files come before the file that includes them.
+fn collect(
+    file: &Path,
+    source: &mut Source,
+    stack: &mut Vec<PathBuf>,
+    documents: &mut Vec<(usize, Mapping)>,
+) -> Result<(), Diagnostic> {
+    let content = std::fs::read_to_string(file).map_err(|e| Diagnostic {
+        file: file.to_path_buf(),
+        location: None,
+        message: e.to_string(),
+    })?;
+    let idx = source.add_file(file, &content);
+
+    let mut mapping = match serde_yaml::from_str(&content).map_err(|e| parse_error(file, e))? {
+        Value::Null => Mapping::new(),
+        Value::Mapping(mapping) => mapping,
+        _ => return Err(source.at_file(idx, "", "the config has to be a hash")),
+    };
+
+    let includes = match mapping.remove("include") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(include)) => vec![include],
+        Some(Value::Sequence(includes)) => includes
+            .into_iter()
+            .enumerate()
+            .map(|(i, include)| match include {
+                Value::String(include) => Ok(include),
+                _ => Err(source.at_file(idx, &format!("include[{}]", i), "has to be a path")),
+            })
+            .collect::<Result<Vec<String>, Diagnostic>>()?,
+        Some(_) => {
+            return Err(source.at_file(idx, "include", "has to be a path or a list of paths"))
+        }
+    };
+
+    stack.push(file.to_path_buf());
+
+    for (i, include) in includes.iter().enumerate() {
+        let path = format!("include[{}]", i);
+        let mut missing = None;
+        let expanded = vars::expand(include, &mut |name| {
+            let value = std::env::var(name).ok();
+
+            if value.is_none() {
+                missing = Some(name.to_string());
+            }
+
+            value
+        });
+
+        if let Some(name) = missing {
+            return Err(source.at_file(
+                idx,
+                &path,
+                format!("the environment variable '{}' isn't set", name),
+            ));
+        }
+
+        // relative paths start at the directory of the file that includes them
+        let included = file
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(expanded);
+
+        if !included.is_file() {
+            return Err(source.at_file(
+                idx,
+                &path,
+                format!("the file '{}' doesn't exist", included.display()),
+            ));
+        }
+
+        if stack.iter().any(|f| is_same_file(f, &included)) {
+            return Err(source.at_file(
+                idx,
+                &path,
+                format!("'{}' ends up including itself", included.display()),
+            ));
+        }
+
+        collect(&included, source, stack, documents)?;
+    }
+
+    stack.pop();
+    documents.push((idx, mapping));
+
+    Ok(())
+}
+
+/// Remembers that the value and everything in it comes from the file
+fn set_origins(value: &Value, path: &str, local_path: &str, file: usize, source: &mut Source) {
+    source.set_origin(path.to_string(), file, local_path.to_string());
+
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                if let Some(key) = key.as_str() {
+                    set_origins(
+                        value,
+                        &join(path, key),
+                        &join(local_path, key),
+                        file,
+                        source,
+                    );
+                }
+            }
+        }
+        Value::Sequence(values) => {
+            for (i, value) in values.iter().enumerate() {
+                set_origins(
+                    value,
+                    &format!("{}[{}]", path, i),
+                    &format!("{}[{}]", local_path, i),
+                    file,
+                    source,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Merges the value of the file into the target. Hashes get merged, lists get appended and
+/// everything else gets replaced. An empty value leaves the target as it is.
+fn merge(
+    target: &mut Value,
+    value: Value,
+    path: &str,
+    local_path: &str,
+    file: usize,
+    source: &mut Source,
+) {
+    match (target, value) {
+        (_, Value::Null) => {}
+        (Value::Mapping(target), Value::Mapping(mapping)) => {
+            for (key, value) in mapping {
+                let name = key.as_str().unwrap_or_default();
+                let (path, local_path) = (join(path, name), join(local_path, name));
+
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value, &path, &local_path, file, source),
+                    None => {
+                        set_origins(&value, &path, &local_path, file, source);
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(target), Value::Sequence(values)) => {
+            let offset = target.len();
+
+            for (i, value) in values.into_iter().enumerate() {
+                set_origins(
+                    &value,
+                    &format!("{}[{}]", path, offset + i),
+                    &format!("{}[{}]", local_path, i),
+                    file,
+                    source,
+                );
+                target.push(value);
+            }
+        }
+        (target, value) => {
+            set_origins(&value, path, local_path, file, source);
+            *target = value;
+        }
+    }
+}
+
+/// Loads the file together with the files it includes and merges them into one config
+pub fn load(file: &Path) -> Result<(Value, Source), Diagnostic> {
+    let mut source = Source::default();
+    let mut documents = Vec::new();
+
+    collect(file, &mut source, &mut Vec::new(), &mut documents)?;
+
+    let mut config = Value::Mapping(Mapping::new());
+
+    for (idx, document) in documents {
+        merge(
+            &mut config,
+            Value::Mapping(document),
+            "",
+            "",
+            idx,
+            &mut source,
+        );
+    }
+
+    Ok((config, source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the files into a directory of their own and loads the first one
+    fn load_files(name: &str, files: &[(&str, &str)]) -> Result<Value, Diagnostic> {
+        let dir = std::env::temp_dir().join(format!("wwm-include-{}-{}", std::process::id(), name));
+
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (file, content) in files {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+
+        load(&dir.join(files[0].0)).map(|(config, _)| config)
+    }
+
+    fn load_error(name: &str, files: &[(&str, &str)]) -> String {
+        match load_files(name, files) {
+            Ok(_) => panic!("expected the config to be invalid"),
+            Err(diagnostic) => diagnostic.message,
+        }
+    }
+
+    #[test]
+    fn lists_get_appended_and_scalars_overridden() {
+        let config = "include: base.yaml\nmargin: 2\nrules:\n  - pattern: b\n";
+        let base = "margin: 1\npadding: 3\nrules:\n  - pattern: a\n";
+        let config = load_files("merge", &[("config.yaml", config), ("base.yaml", base)]).unwrap();
+
+        assert_eq!(
+            config,
+            serde_yaml::from_str::<Value>(
+                "margin: 2\npadding: 3\nrules:\n  - pattern: a\n  - pattern: b\n"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn empty_values_leave_the_base_untouched() {
+        let config = "include: base.yaml\nmargin:\n";
+        let base = "margin: 1\n";
+        let config = load_files("null", &[("config.yaml", config), ("base.yaml", base)]).unwrap();
+
+        assert_eq!(config["margin"], Value::from(1));
+    }
+
+    #[test]
+    fn included_files_come_before_the_file_that_includes_them() {
+        let mut source = Source::default();
+        let mut documents = Vec::new();
+        let dir = std::env::temp_dir().join(format!("wwm-include-{}-order", std::process::id()));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("config.yaml"), "include: [a.yaml, b.yaml]\n").unwrap();
+        std::fs::write(dir.join("a.yaml"), "include: c.yaml\n").unwrap();
+        std::fs::write(dir.join("b.yaml"), "").unwrap();
+        std::fs::write(dir.join("c.yaml"), "").unwrap();
+
+        collect(
+            &dir.join("config.yaml"),
+            &mut source,
+            &mut Vec::new(),
+            &mut documents,
+        )
+        .unwrap();
+
+        let files = source.files();
+        let order: Vec<&str> = documents
+            .iter()
+            .map(|(idx, _)| files[*idx].file_name().unwrap().to_str().unwrap())
+            .collect();
+
+        assert_eq!(order, vec!["c.yaml", "a.yaml", "b.yaml", "config.yaml"]);
+    }
+
+    #[test]
+    fn include_cycles_get_rejected() {
+        let message = load_error(
+            "cycle",
+            &[
+                ("a.yaml", "include: b.yaml\n"),
+                ("b.yaml", "include: a.yaml\n"),
+            ],
+        );
+
+        assert!(
+            message.ends_with("a.yaml' ends up including itself"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn unset_environment_variables_are_an_error() {
+        let message = load_error(
+            "unset_env",
+            &[(
+                "config.yaml",
+                "include:\n  - ${WWM_INCLUDE_TEST_UNSET}/a.yaml\n",
+            )],
+        );
+
+        assert_eq!(
+            message,
+            "include[0]: the environment variable 'WWM_INCLUDE_TEST_UNSET' isn't set"
+        );
+    }
+}
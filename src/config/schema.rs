@@ -0,0 +1,255 @@
+//! The structure of `config.yaml`. Every setting is optional, the defaults get filled in when the
+//! file is turned into a [`Config`](super::Config).
+
+use lazy_static::lazy_static;
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Either a plain string or a hash, e.g. a command line or a launch spec with settings
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringOr<T> {
+    String(String),
+    Hash(T),
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for StringOr<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StringOrVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for StringOrVisitor<T> {
+            type Value = StringOr<T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a string or a hash")
+            }
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(StringOr::String(value.to_string()))
+            }
+            fn visit_map<M: MapAccess<'de>>(self, map: M) -> Result<Self::Value, M::Error> {
+                T::deserialize(de::value::MapAccessDeserializer::new(map)).map(StringOr::Hash)
+            }
+        }
+
+        deserializer.deserialize_any(StringOrVisitor(std::marker::PhantomData))
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ConfigFile {
+    pub app_bar_height: Option<i32>,
+    pub app_bar_bg: Option<i32>,
+    pub app_bar_font: Option<String>,
+    pub app_bar_font_size: Option<i32>,
+    pub work_mode: Option<bool>,
+    pub light_theme: Option<bool>,
+    pub multi_monitor: Option<bool>,
+    pub launch_on_startup: Option<bool>,
+    pub margin: Option<i32>,
+    pub padding: Option<i32>,
+    pub remove_title_bar: Option<bool>,
+    pub remove_task_bar: Option<bool>,
+    pub display_app_bar: Option<bool>,
+    pub manage_dialogs: Option<bool>,
+    pub restore_session: Option<bool>,
+    pub sequence_timeout: Option<i32>,
+    pub show_sequence_hint: Option<bool>,
+    pub excluded_classes: Vec<String>,
+    pub workspaces: Vec<WorkspaceFile>,
+    pub rules: Vec<RuleFile>,
+    pub layouts: Vec<LayoutFile>,
+    pub keybindings: Vec<KeybindingFile>,
+    pub modes: Vec<ModeFile>,
+    pub hooks: Option<HooksFile>,
+    pub autostart: Vec<StringOr<LaunchFile>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WorkspaceFile {
+    pub id: i32,
+    pub monitor: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct InsetsFile {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RectFile {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RuleFile {
+    pub pattern: Option<String>,
+    pub has_custom_titlebar: Option<bool>,
+    pub manage: Option<bool>,
+    pub remove_frame: Option<bool>,
+    pub frame: Option<String>,
+    pub insets: Option<InsetsFile>,
+    pub workspace: Option<i32>,
+    pub monitor: Option<i32>,
+    pub float: Option<bool>,
+    /// A preset or a rect
+    pub geometry: Option<StringOr<RectFile>>,
+    pub always_on_top: Option<bool>,
+    pub opacity: Option<f32>,
+    pub fullscreen: Option<bool>,
+    pub focus: Option<bool>,
+    pub chromium: bool,
+    pub firefox: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct LayoutNodeFile {
+    pub split: Option<String>,
+    pub children: Vec<LayoutNodeFile>,
+    pub title: Option<String>,
+    pub class: Option<String>,
+    pub process: Option<String>,
+    pub cmd: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LayoutFile {
+    pub name: String,
+    pub workspace: Option<i32>,
+    pub root: LayoutNodeFile,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct LaunchFile {
+    pub cmd: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub env: BTreeMap<String, serde_yaml::Value>,
+    pub shell: bool,
+    pub workspace: Option<i32>,
+}
+
+/// A keybinding either has a command or a type with the settings of that type
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct KeybindingFile {
+    pub key: String,
+    pub command: Option<String>,
+    #[serde(rename = "type")]
+    pub typ: Option<String>,
+    pub repeat: Option<bool>,
+    pub cmd: Option<String>,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub env: BTreeMap<String, serde_yaml::Value>,
+    pub shell: bool,
+    pub workspace: Option<i32>,
+    pub name: Option<String>,
+    pub mode: Option<String>,
+    pub id: Option<i32>,
+    pub monitor: Option<i32>,
+    pub direction: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ModeFile {
+    pub name: String,
+    pub keybindings: Vec<KeybindingFile>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct HooksFile {
+    pub on_workspace_change: Option<String>,
+    pub on_window_managed: Option<String>,
+    pub on_focus_change: Option<String>,
+    pub on_config_reload: Option<String>,
+    pub on_work_mode_toggle: Option<String>,
+    pub timeout: Option<i32>,
+}
+
+/// Captures the field names a struct passes to the deserializer
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("not a struct"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+fn field_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+lazy_static! {
+    /// `include` and `vars` get taken out before the rest of the config gets read
+    static ref ROOT_KEYS: Vec<&'static str> = field_names::<ConfigFile>()
+        .iter()
+        .copied()
+        .chain(["include", "vars"])
+        .collect();
+}
+
+/// The settings that are allowed at the path, used to suggest a name for unknown ones
+pub fn known_keys(path: &str) -> &'static [&'static str] {
+    // indices don't matter, e.g. `modes[1].keybindings[3]` is a keybinding like any other
+    let mut shape = String::new();
+    let mut in_index = false;
+
+    for c in path.chars() {
+        match c {
+            '[' => in_index = true,
+            ']' => {
+                in_index = false;
+                shape.push_str("[]");
+            }
+            _ if in_index => {}
+            c => shape.push(c),
+        }
+    }
+
+    match shape.as_str() {
+        "" => &ROOT_KEYS,
+        "workspaces[]" => field_names::<WorkspaceFile>(),
+        "rules[]" => field_names::<RuleFile>(),
+        "rules[].insets" => field_names::<InsetsFile>(),
+        "rules[].geometry" => field_names::<RectFile>(),
+        "layouts[]" => field_names::<LayoutFile>(),
+        "keybindings[]" | "modes[].keybindings[]" => field_names::<KeybindingFile>(),
+        "modes[]" => field_names::<ModeFile>(),
+        "hooks" => field_names::<HooksFile>(),
+        "autostart[]" => field_names::<LaunchFile>(),
+        x if x.starts_with("layouts[].root") => field_names::<LayoutNodeFile>(),
+        _ => &[],
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+/// Something that is wrong with the config, pointing at the place in the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    /// Line and column, both starting at 1
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.file.display(),
+                line,
+                column,
+                self.message
+            ),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+impl Diagnostic {
+    /// The diagnostic with only the name of the file instead of the whole path
+    pub fn short(&self) -> String {
+        Diagnostic {
+            file: self.file.file_name().map(PathBuf::from).unwrap_or_default(),
+            ..self.clone()
+        }
+        .to_string()
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+enum Frame {
+    Mapping { key: Option<String> },
+    Sequence { index: usize },
+}
+
+/// Remembers where every value of a yaml file starts, by its path (e.g. `rules[2].workspace`)
+#[derive(Default)]
+pub struct Positions {
+    positions: HashMap<String, (usize, usize)>,
+    stack: Vec<(String, Frame)>,
+}
+
+impl Positions {
+    pub fn parse(content: &str) -> Self {
+        let mut positions = Self::default();
+
+        // syntax errors get reported by the deserialization
+        let _ = Parser::new(content.chars()).load(&mut positions, false);
+
+        positions
+    }
+    /// Returns the position of the value or of the closest parent that has one
+    pub fn find(&self, path: &str) -> Option<(usize, usize)> {
+        let mut path = path;
+
+        loop {
+            if let Some(position) = self.positions.get(path) {
+                return Some(*position);
+            }
+
+            path = &path[..path.rfind(['.', '['])?];
+        }
+    }
+    /// The path of the node that starts now, None for the key of a mapping
+    fn next_path(&mut self) -> Option<String> {
+        match self.stack.last_mut() {
+            None => Some(String::new()),
+            Some((path, Frame::Sequence { index })) => {
+                *index += 1;
+                Some(format!("{}[{}]", path, *index - 1))
+            }
+            Some((_, Frame::Mapping { key: None })) => None,
+            Some((path, Frame::Mapping { key })) => {
+                let key = key.take().unwrap_or_default();
+
+                Some(match path.is_empty() {
+                    true => key,
+                    false => format!("{}.{}", path, key),
+                })
+            }
+        }
+    }
+}
+
+impl MarkedEventReceiver for Positions {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let position = (marker.line(), marker.col() + 1);
+
+        match event {
+            Event::Scalar(value, ..) => match self.next_path() {
+                Some(path) => {
+                    self.positions.entry(path).or_insert(position);
+                }
+                None => {
+                    let parent = match self.stack.last() {
+                        Some((parent, _)) => parent.clone(),
+                        None => String::new(),
+                    };
+                    let path = match parent.is_empty() {
+                        true => value.clone(),
+                        false => format!("{}.{}", parent, value),
+                    };
+
+                    // keys point at themselves, which is where a wrong value gets looked for as well
+                    self.positions.entry(path).or_insert(position);
+
+                    if let Some((_, Frame::Mapping { key })) = self.stack.last_mut() {
+                        *key = Some(value);
+                    }
+                }
+            },
+            Event::MappingStart(_) | Event::SequenceStart(_) => {
+                let path = self.next_path().unwrap_or_default();
+
+                self.positions.entry(path.clone()).or_insert(position);
+
+                let frame = match event {
+                    Event::MappingStart(_) => Frame::Mapping { key: None },
+                    _ => Frame::Sequence { index: 0 },
+                };
+
+                self.stack.push((path, frame));
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            Event::Alias(_) => {
+                self.next_path();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The files of a config and where the values of the merged config come from
+#[derive(Default)]
+pub struct Source {
+    files: Vec<(PathBuf, Positions)>,
+    /// The file and the path in that file of every value, by its path in the merged config.
+    /// Lists of different files get appended, so e.g. `rules[3]` might be `rules[0]` of a file.
+    origins: HashMap<String, (usize, String)>,
+}
+
+impl Source {
+    /// Remembers the positions of the file and returns its index
+    pub fn add_file(&mut self, file: &Path, content: &str) -> usize {
+        self.files
+            .push((file.to_path_buf(), Positions::parse(content)));
+        self.files.len() - 1
+    }
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|(file, _)| file.clone()).collect()
+    }
+    pub fn set_origin(&mut self, path: String, file: usize, local_path: String) {
+        self.origins.insert(path, (file, local_path));
+    }
+    /// Creates a diagnostic that points at the value of the path in one of the files
+    pub fn at_file(&self, file: usize, path: &str, message: impl Into<String>) -> Diagnostic {
+        let (file, positions) = &self.files[file];
+
+        Diagnostic {
+            file: file.clone(),
+            location: positions.find(path),
+            message: match path.is_empty() {
+                true => message.into(),
+                false => format!("{}: {}", path, message.into()),
+            },
+        }
+    }
+    /// Creates a diagnostic that points at the value of the path in the merged config
+    pub fn at(&self, path: &str, message: impl Into<String>) -> Diagnostic {
+        let mut ancestor = path;
+
+        // values that don't exist (e.g. missing settings) get reported at their parent
+        loop {
+            if let Some((file, local_path)) = self.origins.get(ancestor) {
+                let local_path = format!("{}{}", local_path, &path[ancestor.len()..]);
+                let local_path = local_path.trim_start_matches('.');
+
+                return self.at_file(*file, local_path, message);
+            }
+
+            match ancestor.rfind(['.', '[']) {
+                Some(idx) => ancestor = &ancestor[..idx],
+                None if !ancestor.is_empty() => ancestor = "",
+                None => break,
+            }
+        }
+
+        self.at_file(0, path, message)
+    }
+}
+
+/// Turns an error of serde_yaml into a diagnostic, the location moves from the message into the diagnostic
+pub fn parse_error(file: &Path, error: serde_yaml::Error) -> Diagnostic {
+    let location = error.location();
+    let mut message = error.to_string();
+
+    if let Some(location) = &location {
+        let position = format!(" at line {} column {}", location.line(), location.column());
+
+        message = message.replacen(&position, "", 1);
+    }
+
+    Diagnostic {
+        file: file.to_path_buf(),
+        location: location.map(|l| (l.line(), l.column())),
+        message,
+    }
+}
+
+/// Turns the path of serde_ignored into the same format the positions use
+pub fn format_path(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => format!("{}[{}]", format_path(parent), index),
+        serde_ignored::Path::Map { parent, key } => match format_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => format_path(parent),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + if ca == *cb { 0 } else { 1 };
+
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The candidate that is closest to the misspelt name, if any is close enough
+pub fn suggest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|c| (edit_distance(&name.to_lowercase(), &c.to_lowercase()), *c))
+        .filter(|(distance, c)| *distance <= std::cmp::max(1, c.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// Appends a "did you mean" hint to the message if one of the candidates is close
+pub fn with_suggestion(message: String, name: &str, candidates: &[&str]) -> String {
+    match suggest(name, candidates) {
+        Some(candidate) => format!("{}, did you mean '{}'?", message, candidate),
+        None => message,
+    }
+}
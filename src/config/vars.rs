@@ -0,0 +1,199 @@
+use super::source::{Diagnostic, Source};
+use serde_yaml::{Mapping, Value};
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The name if the text consists of nothing but `$name` or `${name}`
+fn whole_reference(text: &str) -> Option<&str> {
+    let name = text.strip_prefix('$')?;
+    let name = match name.strip_prefix('{') {
+        Some(name) => name.strip_suffix('}')?,
+        None => name,
+    };
+
+    if is_name(name) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Replaces `$name` and `${name}` with what `lookup` returns for the name.
+/// `$$` becomes `$` and names that `lookup` doesn't know stay as they are.
+pub fn expand(text: &str, lookup: &mut dyn FnMut(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+
+    while let Some(idx) = rest.find('$') {
+        expanded.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+
+        let (name, reference) = match rest.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &rest[..end + 2]),
+                None => ("", ""),
+            },
+            None => {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+
+                (&rest[..end], &rest[..end])
+            }
+        };
+
+        let value = if is_name(name) { lookup(name) } else { None };
+
+        match value {
+            Some(value) => expanded.push_str(&value),
+            None => {
+                expanded.push('$');
+                expanded.push_str(reference);
+            }
+        }
+
+        rest = &rest[reference.len()..];
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+fn to_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn substitute(value: &mut Value, vars: &Mapping) {
+    match value {
+        Value::String(text) => {
+            // a value that is only a variable keeps its type, e.g. `margin: $gap`
+            if let Some(var) = whole_reference(text).and_then(|name| vars.get(name)) {
+                *value = var.clone();
+                return;
+            }
+
+            *text = expand(text, &mut |name| vars.get(name).and_then(to_text));
+        }
+        Value::Sequence(values) => {
+            for value in values {
+                substitute(value, vars);
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, value) in mapping.iter_mut() {
+                substitute(value, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Takes the `vars` out of the config and replaces the references to them in every string.
+/// Only include paths look at the environment, names that aren't variables stay as they are.
+pub fn apply(config: &mut Value, source: &Source) -> Result<(), Diagnostic> {
+    let vars = match config.as_mapping_mut().and_then(|m| m.remove("vars")) {
+        None | Some(Value::Null) => Mapping::new(),
+        Some(Value::Mapping(vars)) => vars,
+        Some(_) => return Err(source.at("vars", "has to be a hash")),
+    };
+
+    for (name, value) in vars.iter() {
+        let name = match name.as_str() {
+            Some(name) if is_name(name) => name,
+            _ => {
+                return Err(source.at(
+                    "vars",
+                    "the names of variables can only contain letters, digits and '_'",
+                ))
+            }
+        };
+
+        if to_text(value).is_none() {
+            return Err(source.at(
+                &format!("vars.{}", name),
+                "has to be a string, number or bool",
+            ));
+        }
+    }
+
+    substitute(config, &vars);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "gap" => Some("8".to_string()),
+            _ => None,
+        }
+    }
+
+    fn substituted(config: &str) -> Value {
+        let mut config = serde_yaml::from_str(config).unwrap();
+
+        apply(&mut config, &Source::default()).unwrap();
+        config
+    }
+
+    #[test]
+    fn expands_both_forms() {
+        assert_eq!(expand("$gap ${gap}px", &mut lookup), "8 8px");
+    }
+
+    #[test]
+    fn double_dollar_is_a_literal_dollar() {
+        assert_eq!(expand("$$gap costs $$5", &mut lookup), "$gap costs $5");
+    }
+
+    #[test]
+    fn unknown_and_unterminated_references_stay() {
+        assert_eq!(expand("$other ${other}", &mut lookup), "$other ${other}");
+        assert_eq!(expand("a ${gap", &mut lookup), "a ${gap");
+        assert_eq!(expand("a $", &mut lookup), "a $");
+    }
+
+    #[test]
+    fn whole_references() {
+        assert_eq!(whole_reference("$gap"), Some("gap"));
+        assert_eq!(whole_reference("${gap}"), Some("gap"));
+        assert_eq!(whole_reference("${gap"), None);
+        assert_eq!(whole_reference("$gap px"), None);
+        assert_eq!(whole_reference("gap"), None);
+    }
+
+    #[test]
+    fn whole_references_keep_the_type() {
+        let config = substituted("vars:\n  gap: 8\nmargin: $gap\nborder: ${gap}px\n");
+
+        assert_eq!(
+            config,
+            serde_yaml::from_str::<Value>("margin: 8\nborder: 8px\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn environment_is_not_used() {
+        std::env::set_var("WWM_VARS_TEST", "set");
+
+        let config = substituted("title: $WWM_VARS_TEST\n");
+
+        assert_eq!(config["title"], Value::from("$WWM_VARS_TEST"));
+    }
+}
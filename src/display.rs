@@ -76,21 +76,19 @@ pub fn get_primary_display() -> Display {
         .expect("Couldn't find primary display")
 }
 
-pub fn get_display_by_hmonitor(hmonitor: i32) -> Display {
-    *DISPLAYS
-        .lock()
-        .unwrap()
-        .iter()
-        .find(|d| d.hmonitor == hmonitor)
-        .expect(format!("Couldn't find display with hmonitor of {}", hmonitor).as_str())
-}
-
-pub fn get_display_by_idx(idx: i32) -> Display {
+/// Returns None if there is no display with the index, e.g. because multi_monitor is disabled
+pub fn find_display_by_idx(idx: i32) -> Option<Display> {
     let displays = DISPLAYS.lock().unwrap();
 
-    let x: usize = std::cmp::max(displays.len() - (idx as usize), 0);
+    if idx < 1 || idx as usize > displays.len() {
+        return None;
+    }
+
+    displays.get(displays.len() - idx as usize).copied()
+}
 
-    *displays
-        .get(x)
-        .expect(format!("Couldn't get display at index {}", x).as_str())
+/// Measures the displays again, the grids and app bars still point at the old ones
+pub fn reinit() {
+    DISPLAYS.lock().unwrap().clear();
+    init();
 }
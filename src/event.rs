@@ -1,5 +1,7 @@
 use crate::app_bar::RedrawAppBarReason;
+use crate::command::CommandList;
 use crate::hot_key_manager::Keybinding;
+use crate::ipc::protocol::{Request, Response};
 use crate::win_event_handler::WinEvent;
 use crossbeam_channel::unbounded;
 use crossbeam_channel::Receiver;
@@ -8,9 +10,16 @@ use crossbeam_channel::Sender;
 #[derive(Debug)]
 pub enum Event {
     Keybinding(Keybinding),
+    /// Commands that don't come from a keybinding, e.g. from the menu of the tray icon
+    Command(CommandList),
     WinEvent(WinEvent),
     RedrawAppBar(RedrawAppBarReason),
+    /// Has to be answered through the sender
+    IpcRequest(Request, Sender<Response>),
     ReloadConfig,
+    ReloadScripts,
+    /// Sent when the placeholders of a layout might have waited too long for their windows
+    ExpirePlaceholders,
     Exit,
 }
 
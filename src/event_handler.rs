@@ -1,2 +1,3 @@
-pub mod keybinding;
+pub mod command;
+pub mod ipc;
 pub mod winevent;
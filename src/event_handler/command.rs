@@ -0,0 +1,136 @@
+use crate::change_workspace;
+use crate::command::{Command, CommandList};
+use crate::display::find_display_by_idx;
+use crate::event::Event;
+use crate::history;
+use crate::hot_key_manager;
+use crate::is_visible_workspace;
+use crate::launcher;
+use crate::layout;
+use crate::scripting;
+use crate::CHANNEL;
+use crate::CONFIG;
+use crate::GRIDS;
+use crate::VISIBLE_WORKSPACES;
+use crate::WORKSPACE_ID;
+use log::info;
+
+mod close_tile;
+mod focus;
+mod split;
+mod swap;
+mod toggle_floating_mode;
+pub mod toggle_work_mode;
+
+/// Executes the commands one after another and stops at the first one that fails
+pub fn handle(commands: &CommandList) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Executing {}", commands);
+
+    if commands.changes_layout() {
+        history::record();
+    }
+
+    for command in &commands.0 {
+        execute(command)?;
+    }
+
+    Ok(())
+}
+
+fn execute(command: &Command) -> Result<(), Box<dyn std::error::Error>> {
+    if let Command::MoveWorkspaceToMonitor(_) = command {
+        if !CONFIG.lock().unwrap().multi_monitor {
+            return Ok(());
+        }
+    }
+
+    let sender = CHANNEL.sender.clone();
+    match command.clone() {
+        Command::Launch(spec) => {
+            launcher::spawn(&spec)?;
+        }
+        Command::ApplyLayout(name) => layout::apply(&name)?,
+        Command::EnterMode(name) => hot_key_manager::set_mode(&name),
+        Command::Script(name) => scripting::call(&name)?,
+        Command::Undo => history::undo()?,
+        Command::Redo => history::redo()?,
+        Command::MoveWorkspaceToMonitor(monitor) => {
+            // the command can come from outside, so the monitor might not exist
+            let display = find_display_by_idx(monitor)
+                .ok_or_else(|| format!("monitor {} doesn't exist", monitor))?;
+            let mut grids = GRIDS.lock().unwrap();
+            let mut grid = grids
+                .iter_mut()
+                .find(|g| g.id == *WORKSPACE_ID.lock().unwrap())
+                .unwrap();
+
+            let grid_id = grid.id;
+            let grid_old_monitor = grid.display.hmonitor;
+
+            grid.display = display;
+
+            VISIBLE_WORKSPACES
+                .lock()
+                .unwrap()
+                .insert(grid_old_monitor, 0);
+
+            drop(grids);
+            change_workspace(grid_id)?;
+        }
+        Command::CloseTile => close_tile::handle()?,
+        Command::MoveToWorkspace { id, follow } => {
+            let mut grids = GRIDS.lock().unwrap();
+            let grid = grids
+                .iter_mut()
+                .find(|g| g.id == *WORKSPACE_ID.lock().unwrap())
+                .unwrap();
+
+            if let Some(window_id) = grid.focused_window_id {
+                if let Some(tile) = grid.close_tile_by_window_id(window_id) {
+                    if !follow {
+                        grid.draw_grid();
+                    }
+
+                    let grid = grids.iter_mut().find(|g| g.id == id).unwrap();
+
+                    if follow {
+                        grid.split(tile.window);
+                        drop(grids);
+                        change_workspace(id)?;
+                    } else if is_visible_workspace(id) {
+                        grid.split(tile.window);
+                        grid.draw_grid();
+                    } else {
+                        tile.window.hide();
+                        grid.split(tile.window);
+                    }
+                }
+            }
+        }
+        Command::ChangeWorkspace(id) => change_workspace(id)?,
+        Command::ToggleFloatingMode => toggle_floating_mode::handle()?,
+        Command::ToggleFullscreen => {
+            let mut grids = GRIDS.lock().unwrap();
+            let mut grid = grids
+                .iter_mut()
+                .find(|g| g.id == *WORKSPACE_ID.lock().unwrap())
+                .unwrap();
+
+            if grid.tiles.is_empty() {
+                return Ok(());
+            }
+
+            grid.fullscreen = !grid.fullscreen;
+
+            grid.draw_grid();
+        }
+        Command::ToggleWorkMode => toggle_work_mode::handle()?,
+        Command::Focus(direction) => focus::handle(direction)?,
+        Command::Swap(direction) => swap::handle(direction)?,
+        Command::Quit => sender.send(Event::Exit)?,
+        Command::ReloadConfig => sender.send(Event::ReloadConfig)?,
+        Command::Split(direction) => split::handle(direction)?,
+    };
+
+    Ok(())
+}
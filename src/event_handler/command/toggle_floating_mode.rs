@@ -45,6 +45,12 @@ pub fn handle() -> Result<(), Box<dyn std::error::Error>> {
             grid.close_tile_by_window_id(focused_tile_id);
             grid.draw_grid();
         }
+    } else if let Some(window) = grids
+        .iter_mut()
+        .find_map(|g| g.remove_floating(window_handle as i32))
+    {
+        // the window floats already, so it only stops following its workspace
+        debug!("Unmanaging window '{}' | {}", window.title, window.id);
     } else {
         CHANNEL.sender.clone().send(Event::WinEvent(WinEvent {
             typ: WinEventType::Show(true),
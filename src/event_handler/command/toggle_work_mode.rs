@@ -1,4 +1,5 @@
 use crate::app_bar;
+use crate::event_handler::winevent::show;
 use crate::task_bar;
 use crate::unmanage_everything;
 use crate::win_event_handler;
@@ -34,6 +35,7 @@ pub fn turn_work_mode_on(
     if remove_task_bar {
         task_bar::hide();
     }
+    show::adopt_existing_windows();
     Ok(())
 }
 
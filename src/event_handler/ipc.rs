@@ -0,0 +1,146 @@
+use crate::command::CommandList;
+use crate::config::DEFAULT_MODE;
+use crate::event_handler::command;
+use crate::hot_key_manager;
+use crate::hot_key_manager::Keybinding;
+use crate::ipc::protocol::{
+    ConfigInfo, DisplayInfo, ErrorKind, KeybindingInfo, Request, Response, WindowInfo,
+    WorkspaceInfo, WorkspaceTree,
+};
+use crate::is_visible_workspace;
+use crate::tile_grid::TileGrid;
+use crate::util;
+use crate::CONFIG;
+use crate::DISPLAYS;
+use crate::GRIDS;
+use crate::VISIBLE_WORKSPACES;
+use crate::WORKSPACE_ID;
+use winapi::shared::windef::HWND;
+
+pub fn handle(request: Request) -> Response {
+    match request {
+        Request::RunCommand { command } => match command.parse::<CommandList>() {
+            Ok(commands) => match command::handle(&commands) {
+                Ok(()) => Response::ok(),
+                Err(e) => Response::error(ErrorKind::CommandFailed, e.to_string()),
+            },
+            Err(e) => Response::error(ErrorKind::InvalidCommand, e),
+        },
+        Request::GetWorkspaces => Response::with_data(get_workspaces()),
+        Request::GetTree => Response::with_data(get_tree()),
+        Request::GetDisplays => Response::with_data(get_displays()),
+        Request::GetConfig => Response::with_data(get_config()),
+        // the connection of the client streams the events itself
+        Request::Subscribe { .. } => Response::error(
+            ErrorKind::InvalidRequest,
+            "subscriptions aren't handled by the main loop",
+        ),
+    }
+}
+
+fn workspace_info(grid: &TileGrid) -> WorkspaceInfo {
+    WorkspaceInfo {
+        id: grid.id,
+        display: grid.display.hmonitor,
+        visible: is_visible_workspace(grid.id),
+        focused: grid.id == *WORKSPACE_ID.lock().unwrap(),
+        fullscreen: grid.fullscreen,
+        windows: grid.tiles.len(),
+    }
+}
+
+/// The workspaces that are visible or have windows
+pub fn get_workspaces() -> Vec<WorkspaceInfo> {
+    GRIDS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(workspace_info)
+        .filter(|w| w.visible || w.windows > 0)
+        .collect()
+}
+
+pub fn get_tree() -> Vec<WorkspaceTree> {
+    GRIDS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|grid| WorkspaceTree {
+            workspace: workspace_info(grid),
+            rows: grid.rows,
+            columns: grid.columns,
+            tiles: grid
+                .tiles
+                .iter()
+                .map(|tile| WindowInfo {
+                    id: tile.window.id,
+                    title: tile.window.title.clone(),
+                    class: util::get_class_name_of_window(tile.window.id as HWND)
+                        .unwrap_or_default(),
+                    row: tile.row,
+                    column: tile.column,
+                    split_direction: format!("{:?}", tile.split_direction).to_lowercase(),
+                    focused: grid.focused_window_id == Some(tile.window.id),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+pub fn get_displays() -> Vec<DisplayInfo> {
+    let visible_workspaces = VISIBLE_WORKSPACES.lock().unwrap();
+
+    DISPLAYS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|d| DisplayInfo {
+            id: d.hmonitor,
+            primary: d.is_primary,
+            left: d.left,
+            right: d.right,
+            top: d.top,
+            bottom: d.bottom,
+            // 0 marks a display whose workspace got moved away
+            workspace: visible_workspaces
+                .get(&d.hmonitor)
+                .copied()
+                .filter(|id| *id != 0),
+        })
+        .collect()
+}
+
+fn keybinding_info(mode: &str, kb: &Keybinding) -> KeybindingInfo {
+    KeybindingInfo {
+        mode: mode.to_string(),
+        key: kb.describe(),
+        command: kb.commands.to_string(),
+    }
+}
+
+pub fn get_config() -> ConfigInfo {
+    let config = CONFIG.lock().unwrap();
+
+    ConfigInfo {
+        work_mode: config.work_mode,
+        multi_monitor: config.multi_monitor,
+        display_app_bar: config.display_app_bar,
+        remove_title_bar: config.remove_title_bar,
+        remove_task_bar: config.remove_task_bar,
+        margin: config.margin,
+        padding: config.padding,
+        mode: hot_key_manager::get_mode(),
+        modes: config.modes.iter().map(|m| m.name.clone()).collect(),
+        layouts: config.layouts.iter().map(|l| l.name.clone()).collect(),
+        keybindings: config
+            .keybindings
+            .iter()
+            .map(|kb| keybinding_info(DEFAULT_MODE, kb))
+            .chain(config.modes.iter().flat_map(|m| {
+                m.keybindings
+                    .iter()
+                    .map(move |kb| keybinding_info(&m.name, kb))
+            }))
+            .collect(),
+    }
+}
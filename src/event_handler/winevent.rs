@@ -7,7 +7,8 @@ use winapi::shared::windef::HWND;
 
 mod destroy;
 mod focus_change;
-mod show;
+mod name_change;
+pub mod show;
 
 pub fn handle(ev: WinEvent) -> Result<(), Box<dyn std::error::Error>> {
     let grids = GRIDS.lock().unwrap();
@@ -41,6 +42,7 @@ pub fn handle(ev: WinEvent) -> Result<(), Box<dyn std::error::Error>> {
         WinEventType::Destroy => destroy::handle(ev.hwnd as HWND)?,
         WinEventType::Show(ignore) => show::handle(ev.hwnd as HWND, ignore)?,
         WinEventType::FocusChange => focus_change::handle(ev.hwnd as HWND)?,
+        WinEventType::NameChange => name_change::handle(ev.hwnd as HWND)?,
         WinEventType::Hide => {}
     };
 
@@ -1,9 +1,17 @@
+use crate::journal;
 use crate::GRIDS;
 use crate::WORKSPACE_ID;
 use winapi::shared::windef::HWND;
 
 pub fn handle(hwnd: HWND) -> Result<(), Box<dyn std::error::Error>> {
+    journal::forget(hwnd as i32);
+
     let mut grids = GRIDS.lock().unwrap();
+
+    for grid in grids.iter_mut() {
+        grid.remove_floating(hwnd as i32);
+    }
+
     let grid = grids
         .iter_mut()
         .find(|g| g.id == *WORKSPACE_ID.lock().unwrap())
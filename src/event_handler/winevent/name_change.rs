@@ -0,0 +1,18 @@
+use crate::util;
+use crate::GRIDS;
+use winapi::shared::windef::HWND;
+
+pub fn handle(hwnd: HWND) -> Result<(), Box<dyn std::error::Error>> {
+    // windows are allowed to have no title at all
+    let title = util::get_title_of_window(hwnd).unwrap_or_default();
+    let mut grids = GRIDS.lock().unwrap();
+
+    for grid in grids.iter_mut() {
+        if let Some(tile) = grid.get_tile_by_id_mut(hwnd as i32) {
+            tile.window.title = title;
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -1,21 +1,72 @@
 use crate::change_workspace;
+use crate::config::Rule;
+use crate::display::find_display_by_idx;
+use crate::display::Display;
+use crate::is_visible_workspace;
+use crate::journal;
+use crate::launcher;
+use crate::layout;
 use crate::util;
-use crate::window::gwl_ex_style::GwlExStyle;
+use crate::window::eligibility;
 use crate::window::gwl_style::GwlStyle;
 use crate::window::Window;
 use crate::CONFIG;
 use crate::GRIDS;
+use crate::VISIBLE_WORKSPACES;
 use crate::WORKSPACE_ID;
-use log::debug;
+use log::{debug, info, warn};
 use winapi::shared::windef::HWND;
 
 pub fn handle(hwnd: HWND, ignore_window_style: bool) -> Result<(), Box<dyn std::error::Error>> {
+    manage(hwnd, ignore_window_style, true)
+}
+
+/// Manages every visible window that already exists.
+/// This gets called on startup and when work mode gets turned on again.
+/// A window that can't be managed (e.g. because it got closed in the meantime) gets skipped.
+pub fn adopt_existing_windows() {
+    info!("Adopting existing windows");
+
+    // adopted windows don't take the focus, so the window at the top of the z-order stays focused
+    for hwnd in util::get_top_level_windows() {
+        let is_managed = GRIDS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|g| g.get_tile_by_id(hwnd as i32).is_some() || g.is_floating(hwnd as i32));
+
+        if is_managed || !util::is_window_visible(hwnd) || util::is_window_minimized(hwnd) {
+            continue;
+        }
+
+        if let Err(e) = manage(hwnd, false, false) {
+            warn!("Failed to adopt the window {} {}", hwnd as i32, e);
+        }
+    }
+}
+
+/// `focus` tells whether the window is allowed to take the focus and switch the current workspace.
+fn manage(
+    hwnd: HWND,
+    ignore_window_style: bool,
+    focus: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let title = util::get_title_of_window(hwnd);
 
     if title.is_err() {
         return Ok(());
     }
 
+    // showing a workspace shows its floating windows again, which must not reposition them
+    if GRIDS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|g| g.is_floating(hwnd as i32))
+    {
+        return Ok(());
+    }
+
     let mut window = Window {
         id: hwnd as i32,
         title: title.unwrap(),
@@ -30,12 +81,6 @@ pub fn handle(hwnd: HWND, ignore_window_style: bool) -> Result<(), Box<dyn std::
     window.style = window.original_style;
     window.exstyle = window.get_ex_style().unwrap_or_default();
 
-    let parent = window.get_parent_window();
-
-    let correct_style = ignore_window_style
-        || (window.original_style.contains(GwlStyle::CAPTION)
-            && !window.exstyle.contains(GwlExStyle::DLGMODALFRAME));
-
     for rule in CONFIG.lock().unwrap().rules.clone() {
         if rule.pattern.is_match(&window.title) {
             debug!("Rule({:?}) matched!", rule.pattern);
@@ -44,32 +89,178 @@ pub fn handle(hwnd: HWND, ignore_window_style: bool) -> Result<(), Box<dyn std::
         }
     }
 
-    let rule = window.rule.clone().unwrap_or_default();
-    let should_manage = rule.manage && parent.is_err() && correct_style;
+    let mut rule = window.rule.clone().unwrap_or_default();
+    rule.focus = rule.focus && focus;
+
+    let eligible = match window.get_info() {
+        Ok(info) => {
+            let result = eligibility::check(&info, &CONFIG.lock().unwrap(), ignore_window_style);
+
+            match result {
+                Ok(()) => debug!("Window of class '{}' is eligible", info.class_name),
+                Err(reason) => debug!(
+                    "Window of class '{}' is not eligible, because {}",
+                    info.class_name, reason
+                ),
+            }
+
+            result.is_ok()
+        }
+        Err(_) => false,
+    };
+    let should_manage = rule.manage && eligible;
 
     if should_manage {
-        debug!("Managing window");
-        let mut workspace_id = *WORKSPACE_ID.lock().unwrap();
+        window.original_exstyle = window.exstyle;
+        window.original_rect = window.get_rect()?;
+
+        journal::record(&window);
+
+        let current_workspace_id = *WORKSPACE_ID.lock().unwrap();
+        let mut workspace_id = current_workspace_id;
+        let mut monitor_display = None;
+        // a placeholder reserves a tile, which a floating window would never fill
+        let maybe_placeholder_id = if rule.float {
+            None
+        } else {
+            layout::take_placeholder(&window)
+        };
+        let spawned_workspace = launcher::claim_window(hwnd).and_then(|s| s.workspace);
 
-        if rule.workspace != -1 {
+        if let Some(placeholder_id) = maybe_placeholder_id {
+            if let Some(grid) = GRIDS
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|g| g.tiles.iter().any(|t| t.window.id == placeholder_id))
+            {
+                workspace_id = grid.id;
+            }
+        } else if let Some(id) = spawned_workspace {
+            workspace_id = id;
+        } else if rule.workspace != -1 {
             workspace_id = rule.workspace;
+        } else if rule.monitor != -1 {
+            // the monitor might have been unplugged or multi_monitor is turned off
+            match find_display_by_idx(rule.monitor) {
+                Some(display) => {
+                    match VISIBLE_WORKSPACES.lock().unwrap().get(&display.hmonitor) {
+                        Some(id) if *id != 0 => workspace_id = *id,
+                        _ => debug!("Monitor {} has no visible workspace", rule.monitor),
+                    }
+                    monitor_display = Some(display);
+                }
+                None => warn!(
+                    "Monitor {} doesn't exist, using the current workspace",
+                    rule.monitor
+                ),
+            }
+        }
+
+        if rule.focus && workspace_id != current_workspace_id {
             change_workspace(workspace_id)?;
         }
 
-        if CONFIG.lock().unwrap().remove_title_bar {
-            window.remove_title_bar();
+        if rule.float {
+            return float(window, &rule, workspace_id, monitor_display);
+        }
+
+        debug!("Managing window");
+        let remove_title_bar = CONFIG.lock().unwrap().remove_title_bar;
+
+        if window.apply_frame_policy(remove_title_bar) {
             window.update_style();
         }
 
+        if let Some(opacity) = rule.opacity {
+            window.set_opacity(opacity)?;
+        }
+
         let mut grids = GRIDS.lock().unwrap();
         let grid = grids.iter_mut().find(|g| g.id == workspace_id).unwrap();
+        let previously_focused_id = grid.focused_window_id;
 
-        window.original_rect = window.get_rect()?;
+        match maybe_placeholder_id.and_then(|id| grid.get_tile_by_id_mut(id)) {
+            Some(tile) => {
+                let placeholder_id = tile.window.id;
+
+                tile.window = window;
 
-        grid.split(window);
+                if rule.focus || previously_focused_id == Some(placeholder_id) {
+                    grid.focused_window_id = Some(hwnd as i32);
+                }
+            }
+            None => grid.split(window),
+        }
+
+        if rule.fullscreen {
+            grid.fullscreen = true;
+        } else if !rule.focus
+            && previously_focused_id.is_some()
+            && previously_focused_id != maybe_placeholder_id
+        {
+            grid.focused_window_id = previously_focused_id;
+        }
+
+        if is_visible_workspace(workspace_id) {
+            grid.draw_grid();
 
-        grid.draw_grid();
+            if rule.always_on_top {
+                grid.get_tile_by_id(hwnd as i32)
+                    .unwrap()
+                    .window
+                    .to_foreground(true)?;
+            }
+
+            if !rule.focus {
+                if let Some(tile) = grid.get_focused_tile() {
+                    tile.window.focus()?;
+                }
+            }
+        } else {
+            grid.get_tile_by_id(hwnd as i32).unwrap().window.hide();
+        }
     }
 
     Ok(())
 }
+
+/// Positions a window that should not get tiled based on its rule and adds it to the floating
+/// windows of the workspace, so it gets hidden and shown together with that workspace.
+///
+/// `monitor_display` is the display of the monitor in the rule, if it exists. Otherwise the window
+/// gets positioned on the display of its workspace.
+fn float(
+    mut window: Window,
+    rule: &Rule,
+    workspace_id: i32,
+    monitor_display: Option<Display>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    debug!("Floating window on workspace {}", workspace_id);
+
+    let mut grids = GRIDS.lock().unwrap();
+    let grid = grids.iter_mut().find(|g| g.id == workspace_id).unwrap();
+    let display = monitor_display.unwrap_or(grid.display);
+
+    if let Some(geometry) = rule.geometry {
+        window.set_rect(geometry.calculate(&display, window.original_rect))?;
+    }
+
+    if let Some(opacity) = rule.opacity {
+        window.set_opacity(opacity)?;
+    }
+
+    if is_visible_workspace(workspace_id) {
+        window.to_foreground(rule.always_on_top)?;
+
+        if rule.focus {
+            window.focus()?;
+        }
+    } else {
+        window.hide();
+    }
+
+    grid.floating.push(window);
+
+    Ok(())
+}
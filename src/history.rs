@@ -0,0 +1,251 @@
+use crate::change_workspace;
+use crate::is_visible_workspace;
+use crate::journal;
+use crate::tile::Tile;
+use crate::util;
+use crate::GRIDS;
+use crate::WORKSPACE_ID;
+use lazy_static::lazy_static;
+use log::{debug, info};
+use std::sync::Mutex;
+use winapi::shared::windef::HWND;
+
+/// How many snapshots get kept for undo and redo each
+const LIMIT: usize = 50;
+
+lazy_static! {
+    static ref HISTORY: Mutex<History> = Mutex::new(History::default());
+}
+
+#[derive(Clone)]
+struct SavedTile {
+    tile: Tile,
+    /// Used to detect that the handle belongs to a different window by now
+    class: String,
+}
+
+#[derive(Clone)]
+struct SavedGrid {
+    id: i32,
+    rows: i32,
+    columns: i32,
+    fullscreen: bool,
+    focused_window_id: Option<i32>,
+    tiles: Vec<SavedTile>,
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    workspace_id: i32,
+    grids: Vec<SavedGrid>,
+}
+
+impl Snapshot {
+    fn capture() -> Self {
+        let grids = GRIDS.lock().unwrap();
+
+        Self {
+            workspace_id: *WORKSPACE_ID.lock().unwrap(),
+            grids: grids
+                .iter()
+                .map(|g| SavedGrid {
+                    id: g.id,
+                    rows: g.rows,
+                    columns: g.columns,
+                    fullscreen: g.fullscreen,
+                    focused_window_id: g.focused_window_id,
+                    tiles: g
+                        .tiles
+                        .iter()
+                        .map(|t| SavedTile {
+                            tile: t.clone(),
+                            class: util::get_class_name_of_window(t.window.id as HWND)
+                                .unwrap_or_default(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+    /// Whether both snapshots describe the same arrangement
+    fn same_layout(&self, other: &Snapshot) -> bool {
+        let key = |snapshot: &Snapshot| {
+            snapshot
+                .grids
+                .iter()
+                .map(|g| {
+                    (
+                        g.id,
+                        g.rows,
+                        g.columns,
+                        g.fullscreen,
+                        g.tiles
+                            .iter()
+                            .map(|t| {
+                                (
+                                    t.tile.window.id,
+                                    t.tile.row,
+                                    t.tile.column,
+                                    t.tile.split_direction,
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        key(self) == key(other)
+    }
+}
+
+#[derive(Default)]
+struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+fn push(stack: &mut Vec<Snapshot>, snapshot: Snapshot) {
+    if stack.last().map_or(false, |s| s.same_layout(&snapshot)) {
+        return;
+    }
+
+    stack.push(snapshot);
+
+    if stack.len() > LIMIT {
+        stack.remove(0);
+    }
+}
+
+/// Remembers the current arrangement. Has to be called before the layout gets changed.
+pub fn record() {
+    let snapshot = Snapshot::capture();
+    let mut history = HISTORY.lock().unwrap();
+
+    push(&mut history.undo, snapshot);
+    history.redo.clear();
+}
+
+pub fn undo() -> Result<(), Box<dyn std::error::Error>> {
+    let maybe_snapshot = HISTORY.lock().unwrap().undo.pop();
+
+    if let Some(snapshot) = maybe_snapshot {
+        info!("Undoing the last layout change");
+
+        let current = Snapshot::capture();
+        push(&mut HISTORY.lock().unwrap().redo, current);
+
+        restore(snapshot)?;
+    }
+
+    Ok(())
+}
+
+pub fn redo() -> Result<(), Box<dyn std::error::Error>> {
+    let maybe_snapshot = HISTORY.lock().unwrap().redo.pop();
+
+    if let Some(snapshot) = maybe_snapshot {
+        info!("Redoing the last undone layout change");
+
+        let current = Snapshot::capture();
+        push(&mut HISTORY.lock().unwrap().undo, current);
+
+        restore(snapshot)?;
+    }
+
+    Ok(())
+}
+
+/// Puts every window back where it was in the snapshot.
+///
+/// Windows that got destroyed since are dropped and the grid closes the gap the same way it
+/// does when a window gets closed. Windows that got unmanaged since get managed again and
+/// windows that got managed since stay on the workspace they are on.
+fn restore(snapshot: Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+    let mut grids = GRIDS.lock().unwrap();
+    let mut managed: Vec<(i32, Tile)> = Vec::new();
+
+    for grid in grids.iter_mut() {
+        for tile in grid.tiles.drain(..) {
+            managed.push((grid.id, tile));
+        }
+    }
+
+    for saved_grid in &snapshot.grids {
+        let grid = match grids.iter_mut().find(|g| g.id == saved_grid.id) {
+            Some(grid) => grid,
+            None => continue,
+        };
+        let mut dead_ids = Vec::new();
+
+        grid.rows = saved_grid.rows;
+        grid.columns = saved_grid.columns;
+        grid.fullscreen = saved_grid.fullscreen;
+        grid.focused_window_id = saved_grid.focused_window_id;
+        grid.focus_stack.clear();
+
+        for saved_tile in &saved_grid.tiles {
+            let mut tile = saved_tile.tile.clone();
+            let id = tile.window.id;
+
+            match managed.iter().position(|(_, t)| t.window.id == id) {
+                // the window might have changed since, e.g. its title
+                Some(idx) => tile.window = managed.remove(idx).1.window,
+                None => {
+                    let hwnd = id as HWND;
+                    let alive = !tile.window.is_placeholder()
+                        && util::is_window(hwnd)
+                        && util::get_class_name_of_window(hwnd).unwrap_or_default()
+                            == saved_tile.class;
+
+                    if alive {
+                        debug!("Managing window {} again", id);
+                        journal::record(&tile.window);
+                        tile.window.update_style();
+                        tile.window.update_exstyle();
+                    } else {
+                        debug!("Window {} doesn't exist anymore", id);
+                        dead_ids.push(id);
+                    }
+                }
+            }
+
+            grid.tiles.push(tile);
+        }
+
+        for id in dead_ids {
+            grid.close_tile_by_window_id(id);
+        }
+
+        if grid.tiles.is_empty() {
+            grid.rows = 0;
+            grid.columns = 0;
+            grid.fullscreen = false;
+        } else if grid.get_focused_tile().is_none() {
+            grid.focused_window_id = Some(grid.tiles[0].window.id);
+        }
+    }
+
+    for (grid_id, tile) in managed {
+        if let Some(grid) = grids.iter_mut().find(|g| g.id == grid_id) {
+            grid.split(tile.window);
+        }
+    }
+
+    for grid in grids.iter() {
+        if is_visible_workspace(grid.id) {
+            grid.show();
+            grid.draw_grid();
+        } else {
+            grid.hide();
+        }
+    }
+
+    drop(grids);
+
+    if snapshot.workspace_id != *WORKSPACE_ID.lock().unwrap() {
+        change_workspace(snapshot.workspace_id)?;
+    }
+
+    Ok(())
+}
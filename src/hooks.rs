@@ -0,0 +1,155 @@
+use crate::ipc::protocol::Notification;
+use crate::launcher;
+use crate::CONFIG;
+use log::error;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub fn is_enabled() -> bool {
+    !CONFIG.lock().unwrap().hooks.is_empty()
+}
+
+/// Replaces every `{name}` with the value of the field of the event. The values get quoted when
+/// needed, so a title with spaces or quotes stays a single argument.
+fn fill_placeholders(cmd: &str, notification: &Notification) -> String {
+    let value = serde_json::to_value(notification).unwrap_or_default();
+    let mut fields: HashMap<String, String> = value["data"]
+        .as_object()
+        .map(|data| {
+            data.iter()
+                .map(|(name, value)| match value {
+                    serde_json::Value::String(s) => (name.clone(), s.clone()),
+                    other => (name.clone(), other.to_string()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // every hook that is about a workspace can use `{workspace}`
+    if let Notification::WorkspaceFocused { id, .. } = notification {
+        fields.insert(String::from("workspace"), id.to_string());
+    }
+
+    // a single pass, so placeholders in the values (e.g. a title) stay as they are
+    let mut filled = String::new();
+    let mut rest = cmd;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(len) => start + len,
+            None => break,
+        };
+
+        match fields.get(&rest[start + 1..end]) {
+            Some(text) => {
+                let before = &rest[..start];
+                let after = &rest[end + 1..];
+
+                // a placeholder that is already in quotes would end up quoted twice
+                if before.ends_with('"') && after.starts_with('"') {
+                    filled.push_str(&before[..before.len() - 1]);
+                    filled.push_str(&launcher::wrap_in_quotes(text));
+                    rest = &after[1..];
+                } else {
+                    filled.push_str(before);
+                    filled.push_str(&launcher::quote_arg(text));
+                    rest = after;
+                }
+            }
+            None => {
+                filled.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+/// Runs the hook of the event in the background, if there is one
+pub fn run(notification: &Notification) {
+    let (cmd, timeout) = {
+        let config = CONFIG.lock().unwrap();
+        let hooks = &config.hooks;
+        let hook = match notification {
+            Notification::WorkspaceFocused { .. } => &hooks.on_workspace_change,
+            Notification::WindowManaged { .. } => &hooks.on_window_managed,
+            Notification::WindowFocused { .. } => &hooks.on_focus_change,
+            Notification::ConfigReloaded => &hooks.on_config_reload,
+            Notification::WorkModeChanged { .. } => &hooks.on_work_mode_toggle,
+            _ => &None,
+        };
+
+        match hook {
+            Some(cmd) => (
+                fill_placeholders(cmd, notification),
+                Duration::from_millis(hooks.timeout as u64),
+            ),
+            None => return,
+        }
+    };
+
+    let event = notification.name();
+
+    std::thread::spawn(move || {
+        if let Err(e) = launcher::run(&cmd, timeout) {
+            error!("The {} hook '{}' failed: {}", event, cmd, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_focused_has_a_workspace_placeholder() {
+        let notification = Notification::WorkspaceFocused { id: 3, previous: 1 };
+
+        assert_eq!(
+            fill_placeholders("notify.exe {workspace} {id} {previous}", &notification),
+            "notify.exe 3 3 1"
+        );
+    }
+
+    #[test]
+    fn quotes_values_with_spaces_and_quotes() {
+        let notification = Notification::WindowFocused {
+            id: 42,
+            title: String::from(r#"say "hi" now"#),
+            workspace: 2,
+        };
+
+        assert_eq!(
+            fill_placeholders("bar.exe --title {title} {workspace}", &notification),
+            r#"bar.exe --title "say \"hi\" now" 2"#
+        );
+        assert_eq!(
+            fill_placeholders(r#"bar.exe --title "{title}""#, &notification),
+            r#"bar.exe --title "say \"hi\" now""#
+        );
+    }
+
+    #[test]
+    fn placeholders_in_values_stay() {
+        let notification = Notification::WindowFocused {
+            id: 42,
+            title: String::from("{workspace}"),
+            workspace: 2,
+        };
+
+        assert_eq!(
+            fill_placeholders("bar.exe {title} {workspace}", &notification),
+            "bar.exe {workspace} 2"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_stay() {
+        assert_eq!(
+            fill_placeholders("reload.exe {id}", &Notification::ConfigReloaded),
+            "reload.exe {id}"
+        );
+    }
+}
@@ -1,30 +1,36 @@
+use crate::app_bar::RedrawAppBarReason;
+use crate::command::{Command, CommandList};
+use crate::config::DEFAULT_MODE;
 use crate::event::Event;
-use crate::tile_grid::SplitDirection;
-use crate::util;
+use crate::tray;
 use crate::CHANNEL;
 use crate::CONFIG;
 use crate::WORK_MODE;
-use key::Key;
+use cancel_hook::CancelHook;
+use key::{Chord, KeySpec};
+use keyboard_layout::{ActiveLayout, KeyboardLayout, ResolvedChord};
 use lazy_static::lazy_static;
-use log::{debug, info};
+use log::{debug, error, info};
 use modifier::Modifier;
-use num_traits::FromPrimitive;
+use registry::Registry;
+use sequence::{Matcher, Step};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use strum_macros::EnumString;
 use winapi::shared::windef::HWND;
 use winapi::um::winuser::DispatchMessageW;
 use winapi::um::winuser::PeekMessageW;
-use winapi::um::winuser::RegisterHotKey;
 use winapi::um::winuser::TranslateMessage;
-use winapi::um::winuser::UnregisterHotKey;
 use winapi::um::winuser::MSG;
 use winapi::um::winuser::PM_REMOVE;
 use winapi::um::winuser::WM_HOTKEY;
 
+pub mod cancel_hook;
 pub mod key;
+pub mod keyboard_layout;
 pub mod modifier;
-
-pub type Command = String;
+pub mod registry;
+pub mod sequence;
 
 #[derive(Clone, Copy, EnumString, PartialEq, Debug)]
 pub enum Direction {
@@ -34,86 +40,337 @@ pub enum Direction {
     Down,
 }
 
-#[derive(Display, Debug, Clone, PartialEq)]
-pub enum KeybindingType {
-    CloseTile,
-    Quit,
-    ChangeWorkspace(i32),
-    ToggleFloatingMode,
-    ToggleWorkMode,
-    MoveWorkspaceToMonitor(i32),
-    ToggleFullscreen,
-    Launch(Command),
-    Focus(Direction),
-    Swap(Direction),
-    MoveToWorkspace(i32),
-    Split(SplitDirection),
-}
-
 #[derive(Debug, Clone)]
 pub struct Keybinding {
-    pub typ: KeybindingType,
-    pub key: Key,
-    pub modifier: Modifier,
-    pub registered: bool,
+    pub commands: CommandList,
+    /// The first chord gets registered as hotkey, the remaining ones have to follow after it
+    pub chords: Vec<Chord>,
+    /// Whether the keybinding fires again while the keys are held down
+    pub repeat: bool,
+    /// The chords translated with the keyboard layout that was active when the keybinding got registered
+    pub resolved: Vec<ResolvedChord>,
+    pub registration: Registration,
+}
+
+/// Only compares what comes from the config, the resolved chords and the registration are runtime state
+impl PartialEq for Keybinding {
+    fn eq(&self, other: &Self) -> bool {
+        self.commands == other.commands
+            && self.chords == other.chords
+            && self.repeat == other.repeat
+    }
+}
+
+/// The outcome of registering a keybinding
+#[derive(Debug, Clone, PartialEq)]
+pub enum Registration {
+    Unregistered,
+    /// Contains the id of the hotkey
+    Registered(i32),
+    /// Stays like this until the keybindings get resolved again, e.g. after the keyboard layout changed
+    Failed(String),
+}
+
+impl Keybinding {
+    /// Keybindings that toggle work mode stay registered while work mode is off
+    pub fn toggles_work_mode(&self) -> bool {
+        self.commands.contains(&Command::ToggleWorkMode)
+    }
+    /// The chords of the keybinding the way they are written in the config
+    pub fn describe(&self) -> String {
+        self.chords
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
 }
 
 lazy_static! {
     static ref UNREGISTER: Mutex<bool> = Mutex::new(false);
+    static ref MODE: Mutex<String> = Mutex::new(String::from(DEFAULT_MODE));
+    static ref HINT: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Describes the started key sequence and the chords that can follow
+pub fn get_hint() -> Option<String> {
+    HINT.lock().unwrap().clone()
+}
+
+fn set_hint(hint: Option<String>) {
+    let mut current = HINT.lock().unwrap();
+
+    if *current == hint {
+        return;
+    }
+
+    *current = hint;
+
+    CHANNEL
+        .sender
+        .clone()
+        .send(Event::RedrawAppBar(RedrawAppBarReason::Mode))
+        .expect("Failed to send redraw-app-bar event");
+}
+
+pub fn get_mode() -> String {
+    MODE.lock().unwrap().clone()
+}
+
+/// Activates the keybindings of the given mode. The hot key manager picks up the change on its next iteration.
+pub fn set_mode(name: &str) {
+    let mut mode = MODE.lock().unwrap();
+
+    if *mode == name {
+        return;
+    }
+
+    info!("Entering mode '{}'", name);
+
+    *mode = name.to_string();
+
+    CHANNEL
+        .sender
+        .clone()
+        .send(Event::RedrawAppBar(RedrawAppBarReason::Mode))
+        .expect("Failed to send redraw-app-bar event");
 }
 
-fn unregister_keybindings<'a>(keybindings: impl Iterator<Item = &'a mut Keybinding>) {
+fn get_keybindings_of_mode(name: &str) -> Vec<Keybinding> {
+    let config = CONFIG.lock().unwrap();
+
+    if name == DEFAULT_MODE {
+        return config.keybindings.clone();
+    }
+
+    match config.modes.iter().find(|m| m.name == name) {
+        Some(mode) => mode.keybindings.clone(),
+        None => {
+            error!("Mode '{}' doesn't exist", name);
+            config.keybindings.clone()
+        }
+    }
+}
+
+/// Whether any of the keybindings has to be translated with the keyboard layout
+fn depends_on_layout(keybindings: &[Keybinding]) -> bool {
+    keybindings.iter().any(|kb| {
+        kb.chords
+            .iter()
+            .any(|c| !matches!(c.key, KeySpec::Virtual(_)))
+    })
+}
+
+fn resolve_keybindings(keybindings: &mut [Keybinding], layout: &impl KeyboardLayout) {
     for kb in keybindings {
-        if kb.registered {
-            let key = kb.key as u32;
-            let modifier = kb.modifier.bits();
-            let id = key + modifier;
+        let result: Result<Vec<ResolvedChord>, String> = kb
+            .chords
+            .iter()
+            .map(|c| keyboard_layout::resolve(c, layout))
+            .collect();
+
+        match result {
+            Ok(resolved) => {
+                kb.resolved = resolved;
+                kb.registration = Registration::Unregistered;
+            }
+            Err(e) => {
+                kb.resolved = Vec::new();
+                kb.registration = Registration::Failed(e);
+            }
+        }
+    }
+}
+
+/// Fails keybindings that can never fire, because an earlier keybinding uses the same keys or
+/// because they start with a shorter sequence, which always wins.
+fn detect_conflicts(keybindings: &mut [Keybinding]) {
+    for idx in 0..keybindings.len() {
+        let kb = &keybindings[idx];
+
+        if kb.resolved.is_empty() {
+            continue;
+        }
+
+        let conflict = keybindings
+            .iter()
+            .enumerate()
+            .filter(|(other_idx, other)| *other_idx != idx && !other.resolved.is_empty())
+            .find(|(other_idx, other)| {
+                (other.resolved == kb.resolved && *other_idx < idx)
+                    || (other.resolved.len() < kb.resolved.len()
+                        && kb.resolved.starts_with(&other.resolved))
+            })
+            .map(|(_, other)| {
+                format!(
+                    "conflicts with Keybinding({}, {})",
+                    other.describe(),
+                    other.commands
+                )
+            });
+
+        if let Some(reason) = conflict {
+            keybindings[idx].registration = Registration::Failed(reason);
+        }
+    }
+}
+
+/// Describes every keybinding that failed
+fn collect_failures(keybindings: &[Keybinding]) -> Vec<String> {
+    keybindings
+        .iter()
+        .filter_map(|kb| match &kb.registration {
+            Registration::Failed(reason) => {
+                Some(format!("{} ({}): {}", kb.describe(), kb.commands, reason))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Logs the failed keybindings and tells the user about them
+fn report_failures(failures: &[String]) {
+    if failures.is_empty() {
+        return;
+    }
 
-            kb.registered = false;
+    for failure in failures {
+        error!("Keybinding {}", failure);
+    }
+
+    tray::notify(
+        &format!("{} keybinding(s) are not available", failures.len()),
+        &failures.join("\n"),
+    );
+}
 
+fn unregister_keybindings<'a>(
+    registry: &mut Registry,
+    keybindings: impl Iterator<Item = &'a mut Keybinding>,
+) {
+    for kb in keybindings {
+        if let Registration::Registered(_) = kb.registration {
             info!(
-                "Unregistering Keybinding({}+{}, {})",
-                format!("{:?}", kb.modifier).replace(" | ", "+"),
-                kb.key,
-                kb.typ
+                "Unregistering Keybinding({}, {})",
+                kb.describe(),
+                kb.commands
             );
 
-            unsafe {
-                UnregisterHotKey(0 as HWND, id as i32);
-            }
+            registry.release(&kb.resolved[0]);
+            kb.registration = Registration::Unregistered;
         }
     }
 }
 
-fn register_keybindings<'a>(keybindings: impl Iterator<Item = &'a mut Keybinding>) {
+/// Returns the keybindings that failed to register
+fn register_keybindings<'a>(
+    registry: &mut Registry,
+    keybindings: impl Iterator<Item = &'a mut Keybinding>,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+
     for kb in keybindings {
-        if !kb.registered {
-            let key = kb.key as u32;
-            let modifier = kb.modifier.bits();
-            let id = key + modifier;
+        if kb.registration == Registration::Unregistered {
+            info!("Registering Keybinding({}, {})", kb.describe(), kb.commands);
 
-            kb.registered = true;
+            // sequences can share their first chord, the registry only registers it once
+            kb.registration = match registry.acquire(kb.resolved[0], kb.repeat) {
+                Ok(id) => Registration::Registered(id),
+                Err(e) => {
+                    failures.push(format!("{} ({}): {}", kb.describe(), kb.commands, e));
+                    Registration::Failed(e)
+                }
+            };
+        }
+    }
 
-            info!(
-                "Registering Keybinding({}+{}, {})",
-                format!("{:?}", kb.modifier).replace(" | ", "+"),
-                kb.key,
-                kb.typ
-            );
+    failures
+}
 
-            unsafe {
-                util::winapi_nullable_to_result(RegisterHotKey(
-                    0 as HWND, id as i32, modifier, key,
-                ))
-                .expect("Failed to register keybinding");
-            }
+/// Registers the chords that can follow in a pending key sequence as hotkeys, so they don't reach
+/// the focused window.
+fn register_temporary_chords(
+    registry: &mut Registry,
+    chords: &[ResolvedChord],
+) -> Vec<ResolvedChord> {
+    let mut acquired = Vec::new();
+
+    for chord in chords {
+        // holding down a key of a sequence shouldn't skip the following steps
+        match registry.acquire(*chord, false) {
+            Ok(_) => acquired.push(*chord),
+            Err(e) => error!("Failed to register temporary hotkey: {}", e),
         }
     }
+
+    acquired
+}
+
+fn unregister_temporary_chords(registry: &mut Registry, chords: &mut Vec<ResolvedChord>) {
+    for chord in chords.drain(..) {
+        registry.release(&chord);
+    }
+}
+
+fn create_matcher(keybindings: &[Keybinding]) -> Matcher<ResolvedChord> {
+    let timeout = CONFIG.lock().unwrap().sequence_timeout;
+
+    Matcher::new(
+        keybindings
+            .iter()
+            .map(|kb| match kb.registration {
+                // an empty sequence never matches, but keeps the indices in line with the keybindings
+                Registration::Failed(_) => Vec::new(),
+                _ => kb.resolved.clone(),
+            })
+            .collect(),
+        Duration::from_millis(timeout as u64),
+    )
+}
+
+fn create_hint(matcher: &Matcher<ResolvedChord>, next: &[ResolvedChord]) -> Option<String> {
+    if !CONFIG.lock().unwrap().show_sequence_hint {
+        return None;
+    }
+
+    let join = |chords: &[ResolvedChord], separator: &str| {
+        chords
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>()
+            .join(separator)
+    };
+
+    Some(format!(
+        "{}: {}",
+        join(matcher.pressed(), ", "),
+        join(next, " | ")
+    ))
+}
+
+/// Translates the keybindings with the layout and returns the ones that can't be used
+fn prepare_keybindings(
+    keybindings: &mut [Keybinding],
+    layout: &impl KeyboardLayout,
+) -> Vec<String> {
+    resolve_keybindings(keybindings, layout);
+    detect_conflicts(keybindings);
+
+    collect_failures(keybindings)
 }
 
 pub fn register() -> Result<(), Box<dyn std::error::Error>> {
     std::thread::spawn(|| {
-        let mut keybindings = CONFIG.lock().unwrap().keybindings.clone();
+        set_mode(DEFAULT_MODE);
+
+        let mut mode = get_mode();
+        let mut layout = ActiveLayout::get();
+        let mut keybindings = get_keybindings_of_mode(&mode);
+        let mut failures = prepare_keybindings(&mut keybindings, &layout);
+        let mut matcher = create_matcher(&keybindings);
+        let mut registry = Registry::default();
+        let mut temporary_chords: Vec<ResolvedChord> = Vec::new();
+        let mut cancel_hook: Option<CancelHook> = None;
         let mut msg: MSG = MSG::default();
 
         while *UNREGISTER.lock().unwrap() {
@@ -123,20 +380,24 @@ pub fn register() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         if *WORK_MODE.lock().unwrap() {
-            register_keybindings(keybindings.iter_mut());
+            failures.extend(register_keybindings(&mut registry, keybindings.iter_mut()));
         } else {
-            register_keybindings(
-                keybindings
-                    .iter_mut()
-                    .filter(|kb| kb.typ == KeybindingType::ToggleWorkMode),
-            );
+            failures.extend(register_keybindings(
+                &mut registry,
+                keybindings.iter_mut().filter(|kb| kb.toggles_work_mode()),
+            ));
         }
 
+        report_failures(&failures);
+
         unsafe {
             loop {
                 if *UNREGISTER.lock().unwrap() {
                     debug!("Unregistering hot key manager");
-                    unregister_keybindings(keybindings.iter_mut());
+                    unregister_keybindings(&mut registry, keybindings.iter_mut());
+                    unregister_temporary_chords(&mut registry, &mut temporary_chords);
+                    drop(cancel_hook.take());
+                    set_hint(None);
                     *UNREGISTER.lock().unwrap() = false;
                     break;
                 }
@@ -146,11 +407,29 @@ pub fn register() -> Result<(), Box<dyn std::error::Error>> {
                     DispatchMessageW(&msg);
 
                     if msg.message == WM_HOTKEY {
-                        let modifier = Modifier::from_bits((msg.lParam & 0xffff) as u32).unwrap();
+                        let mut modifier =
+                            Modifier::from_bits_truncate((msg.lParam & 0xffff) as u32);
+
+                        modifier.remove(Modifier::NOREPEAT);
+
+                        let chord = ResolvedChord {
+                            modifier,
+                            virtual_key: ((msg.lParam >> 16) & 0xffff) as u32,
+                        };
+
+                        let step = matcher.feed(chord, Instant::now());
+
+                        unregister_temporary_chords(&mut registry, &mut temporary_chords);
+                        cancel_hook = None;
 
-                        if let Some(key) = Key::from_isize(msg.lParam >> 16) {
-                            for kb in &keybindings {
-                                if kb.key == key && kb.modifier == modifier {
+                        match step {
+                            Step::Matched(idx) => {
+                                set_hint(None);
+
+                                let kb = &keybindings[idx];
+
+                                // in case a sequence of ToggleWorkMode shares its first chord with other keybindings
+                                if *WORK_MODE.lock().unwrap() || kb.toggles_work_mode() {
                                     CHANNEL
                                         .sender
                                         .clone()
@@ -158,21 +437,84 @@ pub fn register() -> Result<(), Box<dyn std::error::Error>> {
                                         .expect("Failed to send key event");
                                 }
                             }
+                            Step::Pending(next) => {
+                                temporary_chords = register_temporary_chords(&mut registry, &next);
+                                set_hint(create_hint(&matcher, &next));
+
+                                match CancelHook::install(&next, Some(chord.virtual_key)) {
+                                    Ok(hook) => cancel_hook = Some(hook),
+                                    Err(e) => error!("Failed to watch the keyboard: {}", e),
+                                }
+                            }
+                            Step::Cancelled => set_hint(None),
                         }
                     }
                 }
 
+                if cancel_hook
+                    .as_ref()
+                    .is_some_and(|hook| hook.take_cancelled())
+                {
+                    debug!("Key sequence cancelled");
+                    matcher.reset();
+                    unregister_temporary_chords(&mut registry, &mut temporary_chords);
+                    cancel_hook = None;
+                    set_hint(None);
+                }
+
                 let work_mode = *WORK_MODE.lock().unwrap();
+
+                if !work_mode {
+                    set_mode(DEFAULT_MODE);
+                }
+
+                let current_mode = get_mode();
+                let mut failures = Vec::new();
+
+                if matcher.tick(Instant::now()) {
+                    debug!("Key sequence timed out");
+                    unregister_temporary_chords(&mut registry, &mut temporary_chords);
+                    cancel_hook = None;
+                    set_hint(None);
+                }
+
+                if current_mode != mode {
+                    unregister_keybindings(&mut registry, keybindings.iter_mut());
+                    unregister_temporary_chords(&mut registry, &mut temporary_chords);
+                    cancel_hook = None;
+                    set_hint(None);
+                    keybindings = get_keybindings_of_mode(&current_mode);
+                    failures = prepare_keybindings(&mut keybindings, &layout);
+                    matcher = create_matcher(&keybindings);
+                    mode = current_mode;
+                }
+
+                if depends_on_layout(&keybindings) {
+                    let current_layout = ActiveLayout::get();
+
+                    if current_layout != layout {
+                        info!("Keyboard layout changed. Resolving keybindings again");
+                        unregister_keybindings(&mut registry, keybindings.iter_mut());
+                        unregister_temporary_chords(&mut registry, &mut temporary_chords);
+                        cancel_hook = None;
+                        set_hint(None);
+                        layout = current_layout;
+                        failures = prepare_keybindings(&mut keybindings, &layout);
+                        matcher = create_matcher(&keybindings);
+                    }
+                }
+
                 if !work_mode {
                     unregister_keybindings(
-                        keybindings
-                            .iter_mut()
-                            .filter(|kb| kb.typ != KeybindingType::ToggleWorkMode),
+                        &mut registry,
+                        keybindings.iter_mut().filter(|kb| !kb.toggles_work_mode()),
                     );
                 } else {
-                    register_keybindings(keybindings.iter_mut());
+                    failures.extend(register_keybindings(&mut registry, keybindings.iter_mut()));
                 }
 
+                report_failures(&failures);
+
                 std::thread::sleep(std::time::Duration::from_millis(5));
             }
         }
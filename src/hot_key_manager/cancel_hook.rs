@@ -0,0 +1,218 @@
+use super::keyboard_layout::ResolvedChord;
+use super::modifier::Modifier;
+use crate::util;
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use winapi::ctypes::c_int;
+use winapi::shared::minwindef::LPARAM;
+use winapi::shared::minwindef::LRESULT;
+use winapi::shared::minwindef::WPARAM;
+use winapi::shared::windef::HHOOK;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::CallNextHookEx;
+use winapi::um::winuser::GetAsyncKeyState;
+use winapi::um::winuser::SetWindowsHookExW;
+use winapi::um::winuser::UnhookWindowsHookEx;
+use winapi::um::winuser::HC_ACTION;
+use winapi::um::winuser::KBDLLHOOKSTRUCT;
+use winapi::um::winuser::VK_CONTROL;
+use winapi::um::winuser::VK_LCONTROL;
+use winapi::um::winuser::VK_LMENU;
+use winapi::um::winuser::VK_LSHIFT;
+use winapi::um::winuser::VK_LWIN;
+use winapi::um::winuser::VK_MENU;
+use winapi::um::winuser::VK_RCONTROL;
+use winapi::um::winuser::VK_RMENU;
+use winapi::um::winuser::VK_RSHIFT;
+use winapi::um::winuser::VK_RWIN;
+use winapi::um::winuser::VK_SHIFT;
+use winapi::um::winuser::WH_KEYBOARD_LL;
+use winapi::um::winuser::WM_KEYDOWN;
+use winapi::um::winuser::WM_KEYUP;
+use winapi::um::winuser::WM_SYSKEYDOWN;
+use winapi::um::winuser::WM_SYSKEYUP;
+
+lazy_static! {
+    static ref WATCH: Mutex<Watch> = Mutex::new(Watch::default());
+}
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+const MODIFIER_KEYS: [c_int; 11] = [
+    VK_SHIFT,
+    VK_LSHIFT,
+    VK_RSHIFT,
+    VK_CONTROL,
+    VK_LCONTROL,
+    VK_RCONTROL,
+    VK_MENU,
+    VK_LMENU,
+    VK_RMENU,
+    VK_LWIN,
+    VK_RWIN,
+];
+
+/// Decides which key presses cancel a pending key sequence
+#[derive(Default)]
+struct Watch {
+    /// The chords that continue the sequence, they arrive as hotkeys
+    next: Vec<ResolvedChord>,
+    /// The key of the last chord, which repeats as long as it is held down
+    held: Option<u32>,
+}
+
+impl Watch {
+    /// Returns whether the key cancels the sequence
+    fn on_key(&mut self, virtual_key: u32, down: bool, modifier: Modifier) -> bool {
+        if !down {
+            if self.held == Some(virtual_key) {
+                self.held = None;
+            }
+
+            return false;
+        }
+
+        if self.held == Some(virtual_key) || MODIFIER_KEYS.contains(&(virtual_key as c_int)) {
+            return false;
+        }
+
+        !self.next.contains(&ResolvedChord {
+            modifier,
+            virtual_key,
+        })
+    }
+}
+
+fn is_down(virtual_key: c_int) -> bool {
+    unsafe { GetAsyncKeyState(virtual_key) < 0 }
+}
+
+fn current_modifier() -> Modifier {
+    let mut modifier = Modifier::empty();
+
+    modifier.set(Modifier::ALT, is_down(VK_MENU));
+    modifier.set(Modifier::CONTROL, is_down(VK_CONTROL));
+    modifier.set(Modifier::SHIFT, is_down(VK_SHIFT));
+    modifier.set(Modifier::WIN, is_down(VK_LWIN) || is_down(VK_RWIN));
+
+    modifier
+}
+
+unsafe extern "system" fn hook_proc(code: c_int, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if code == HC_ACTION {
+        let info = &*(l_param as *const KBDLLHOOKSTRUCT);
+        let down = match w_param as u32 {
+            WM_KEYDOWN | WM_SYSKEYDOWN => Some(true),
+            WM_KEYUP | WM_SYSKEYUP => Some(false),
+            _ => None,
+        };
+
+        if let Some(down) = down {
+            if WATCH
+                .lock()
+                .unwrap()
+                .on_key(info.vkCode, down, current_modifier())
+            {
+                CANCELLED.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    // the key still reaches the focused window
+    CallNextHookEx(std::ptr::null_mut(), code, w_param, l_param)
+}
+
+/// A low-level keyboard hook that lives as long as a key sequence is pending, so that a key which
+/// can't continue the sequence cancels it instead of waiting for the timeout.
+///
+/// The hook runs on the thread that installs it, which has to keep pumping messages.
+pub struct CancelHook {
+    hook: HHOOK,
+}
+
+impl CancelHook {
+    pub fn install(
+        next: &[ResolvedChord],
+        held: Option<u32>,
+    ) -> Result<Self, util::WinApiResultError> {
+        *WATCH.lock().unwrap() = Watch {
+            next: next.to_vec(),
+            held,
+        };
+        CANCELLED.store(false, Ordering::SeqCst);
+
+        let hook = unsafe {
+            util::winapi_ptr_to_result(SetWindowsHookExW(
+                WH_KEYBOARD_LL,
+                Some(hook_proc),
+                GetModuleHandleW(std::ptr::null()),
+                0,
+            ))?
+        };
+
+        Ok(Self { hook })
+    }
+    /// Returns whether a key cancelled the sequence since the last call
+    pub fn take_cancelled(&self) -> bool {
+        CANCELLED.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Drop for CancelHook {
+    fn drop(&mut self) {
+        unsafe {
+            UnhookWindowsHookEx(self.hook);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const K: u32 = 0x4B;
+    const L: u32 = 0x4C;
+
+    fn watch(held: Option<u32>) -> Watch {
+        Watch {
+            next: vec![ResolvedChord {
+                modifier: Modifier::ALT,
+                virtual_key: L,
+            }],
+            held,
+        }
+    }
+
+    #[test]
+    fn next_chord_does_not_cancel() {
+        assert!(!watch(None).on_key(L, true, Modifier::ALT));
+    }
+
+    #[test]
+    fn other_key_cancels() {
+        assert!(watch(None).on_key(K, true, Modifier::ALT));
+    }
+
+    #[test]
+    fn next_key_with_other_modifiers_cancels() {
+        assert!(watch(None).on_key(L, true, Modifier::empty()));
+    }
+
+    #[test]
+    fn modifiers_do_not_cancel() {
+        let mut watch = watch(None);
+
+        assert!(!watch.on_key(VK_LMENU as u32, true, Modifier::ALT));
+        assert!(!watch.on_key(VK_RSHIFT as u32, true, Modifier::SHIFT));
+    }
+
+    #[test]
+    fn held_key_only_cancels_after_it_got_released() {
+        let mut watch = watch(Some(K));
+
+        assert!(!watch.on_key(K, true, Modifier::ALT));
+        assert!(!watch.on_key(K, false, Modifier::ALT));
+        assert!(watch.on_key(K, true, Modifier::ALT));
+    }
+}
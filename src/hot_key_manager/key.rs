@@ -1,11 +1,49 @@
+use super::modifier::Modifier;
 use strum_macros::EnumString;
 
+/// Virtual key codes that can be used in keybindings.
+/// The punctuation keys are named after their position on a US keyboard.
 #[derive(Clone, Copy, FromPrimitive, ToPrimitive, PartialEq, EnumString, Display, Debug)]
 #[allow(dead_code)]
 pub enum Key {
+    Backspace = 0x08,
+    Tab = 0x09,
     Enter = 0x0D,
-    Plus = 0xBB,
-    Minus = 0xBD,
+    Pause = 0x13,
+    CapsLock = 0x14,
+    Escape = 0x1B,
+    Space = 0x20,
+    PageUp = 0x21,
+    PageDown = 0x22,
+    End = 0x23,
+    Home = 0x24,
+    Left = 0x25,
+    Up = 0x26,
+    Right = 0x27,
+    Down = 0x28,
+    Print = 0x2C,
+    Insert = 0x2D,
+    Delete = 0x2E,
+    #[strum(serialize = "0")]
+    Zero = 0x30,
+    #[strum(serialize = "1")]
+    One = 0x31,
+    #[strum(serialize = "2")]
+    Two = 0x32,
+    #[strum(serialize = "3")]
+    Three = 0x33,
+    #[strum(serialize = "4")]
+    Four = 0x34,
+    #[strum(serialize = "5")]
+    Five = 0x35,
+    #[strum(serialize = "6")]
+    Six = 0x36,
+    #[strum(serialize = "7")]
+    Seven = 0x37,
+    #[strum(serialize = "8")]
+    Eight = 0x38,
+    #[strum(serialize = "9")]
+    Nine = 0x39,
     A = 0x41,
     B = 0x42,
     C = 0x43,
@@ -32,28 +70,128 @@ pub enum Key {
     X = 0x58,
     Y = 0x59,
     Z = 0x5A,
-    Left = 0x25,
-    Up = 0x26,
-    Right = 0x27,
-    Down = 0x28,
-    #[strum(serialize = "0")]
-    Zero = 0x30,
-    #[strum(serialize = "1")]
-    One = 0x31,
-    #[strum(serialize = "2")]
-    Two = 0x32,
-    #[strum(serialize = "3")]
-    Three = 0x33,
-    #[strum(serialize = "4")]
-    Four = 0x34,
-    #[strum(serialize = "5")]
-    Five = 0x35,
-    #[strum(serialize = "6")]
-    Six = 0x36,
-    #[strum(serialize = "7")]
-    Seven = 0x37,
-    #[strum(serialize = "8")]
-    Eight = 0x38,
-    #[strum(serialize = "9")]
-    Nine = 0x39,
+    Apps = 0x5D,
+    Sleep = 0x5F,
+    Numpad0 = 0x60,
+    Numpad1 = 0x61,
+    Numpad2 = 0x62,
+    Numpad3 = 0x63,
+    Numpad4 = 0x64,
+    Numpad5 = 0x65,
+    Numpad6 = 0x66,
+    Numpad7 = 0x67,
+    Numpad8 = 0x68,
+    Numpad9 = 0x69,
+    NumpadMultiply = 0x6A,
+    NumpadAdd = 0x6B,
+    NumpadSeparator = 0x6C,
+    NumpadSubtract = 0x6D,
+    NumpadDecimal = 0x6E,
+    NumpadDivide = 0x6F,
+    F1 = 0x70,
+    F2 = 0x71,
+    F3 = 0x72,
+    F4 = 0x73,
+    F5 = 0x74,
+    F6 = 0x75,
+    F7 = 0x76,
+    F8 = 0x77,
+    F9 = 0x78,
+    F10 = 0x79,
+    F11 = 0x7A,
+    F12 = 0x7B,
+    F13 = 0x7C,
+    F14 = 0x7D,
+    F15 = 0x7E,
+    F16 = 0x7F,
+    F17 = 0x80,
+    F18 = 0x81,
+    F19 = 0x82,
+    F20 = 0x83,
+    F21 = 0x84,
+    F22 = 0x85,
+    F23 = 0x86,
+    F24 = 0x87,
+    NumLock = 0x90,
+    ScrollLock = 0x91,
+    BrowserBack = 0xA6,
+    BrowserForward = 0xA7,
+    BrowserRefresh = 0xA8,
+    BrowserStop = 0xA9,
+    BrowserSearch = 0xAA,
+    BrowserFavorites = 0xAB,
+    BrowserHome = 0xAC,
+    VolumeMute = 0xAD,
+    VolumeDown = 0xAE,
+    VolumeUp = 0xAF,
+    MediaNext = 0xB0,
+    MediaPrevious = 0xB1,
+    MediaStop = 0xB2,
+    MediaPlayPause = 0xB3,
+    LaunchMail = 0xB4,
+    LaunchMediaSelect = 0xB5,
+    LaunchApp1 = 0xB6,
+    LaunchApp2 = 0xB7,
+    Semicolon = 0xBA,
+    Plus = 0xBB,
+    Comma = 0xBC,
+    Minus = 0xBD,
+    Period = 0xBE,
+    Slash = 0xBF,
+    Backquote = 0xC0,
+    LeftBracket = 0xDB,
+    Backslash = 0xDC,
+    RightBracket = 0xDD,
+    Quote = 0xDE,
+    IntlBackslash = 0xE2,
+}
+
+/// How the key of a chord is written in the config
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeySpec {
+    /// A virtual key. The punctuation keys depend on the keyboard layout.
+    Virtual(Key),
+    /// A physical key, written as `sc:0x0C`
+    ScanCode(u32),
+    /// The key that types the character on the active keyboard layout, written as `char:+`
+    Character(char),
+}
+
+impl std::fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeySpec::Virtual(key) => write!(f, "{}", key),
+            KeySpec::ScanCode(scan_code) => write!(f, "sc:0x{:02X}", scan_code),
+            KeySpec::Character(c) => write!(f, "char:{}", c),
+        }
+    }
+}
+
+/// A single key press together with the modifiers that are held down
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Chord {
+    pub modifier: Modifier,
+    pub key: KeySpec,
+}
+
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", modifier_prefix(self.modifier), self.key)
+    }
+}
+
+/// Formats the modifiers the same way they are written in the config, e.g. `Alt+Shift+`
+pub fn modifier_prefix(modifier: Modifier) -> String {
+    let names = [
+        (Modifier::ALT, "Alt"),
+        (Modifier::CONTROL, "Control"),
+        (Modifier::SHIFT, "Shift"),
+        (Modifier::WIN, "Win"),
+    ];
+
+    names
+        .iter()
+        .filter(|(flag, _)| modifier.contains(*flag))
+        .map(|(_, name)| format!("{}+", name))
+        .collect()
 }
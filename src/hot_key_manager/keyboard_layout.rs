@@ -0,0 +1,343 @@
+use super::key::{modifier_prefix, Chord, Key, KeySpec};
+use super::modifier::Modifier;
+use num_traits::FromPrimitive;
+use winapi::shared::minwindef::HKL;
+use winapi::um::winuser::GetForegroundWindow;
+use winapi::um::winuser::GetKeyboardLayout;
+use winapi::um::winuser::GetWindowThreadProcessId;
+use winapi::um::winuser::MapVirtualKeyExW;
+use winapi::um::winuser::VkKeyScanExW;
+use winapi::um::winuser::MAPVK_VSC_TO_VK_EX;
+
+/// A chord after its key got translated to a virtual key, which is what RegisterHotKey expects
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ResolvedChord {
+    pub modifier: Modifier,
+    pub virtual_key: u32,
+}
+
+impl std::fmt::Display for ResolvedChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match Key::from_u32(self.virtual_key) {
+            Some(key) => write!(f, "{}{}", modifier_prefix(self.modifier), key),
+            None => write!(
+                f,
+                "{}0x{:02X}",
+                modifier_prefix(self.modifier),
+                self.virtual_key
+            ),
+        }
+    }
+}
+
+/// Translates physical keys and characters to virtual keys.
+pub trait KeyboardLayout {
+    fn virtual_key_of_scan_code(&self, scan_code: u32) -> Option<u32>;
+    /// Returns the virtual key and the modifiers that have to be held down to type the character
+    fn virtual_key_of_char(&self, c: char) -> Option<(u32, Modifier)>;
+}
+
+/// Translates the key of the chord with the given layout.
+/// The modifiers needed to type a character get added to the modifiers of the chord.
+pub fn resolve(chord: &Chord, layout: &impl KeyboardLayout) -> Result<ResolvedChord, String> {
+    match chord.key {
+        KeySpec::Virtual(key) => Ok(ResolvedChord {
+            modifier: chord.modifier,
+            virtual_key: key as u32,
+        }),
+        KeySpec::ScanCode(scan_code) => layout
+            .virtual_key_of_scan_code(scan_code)
+            .map(|virtual_key| ResolvedChord {
+                modifier: chord.modifier,
+                virtual_key,
+            })
+            .ok_or_else(|| format!("'{}' has no key on the keyboard layout", chord)),
+        KeySpec::Character(c) => layout
+            .virtual_key_of_char(c)
+            .map(|(virtual_key, modifier)| ResolvedChord {
+                modifier: chord.modifier | modifier,
+                virtual_key,
+            })
+            .ok_or_else(|| format!("'{}' can't be typed on the keyboard layout", chord)),
+    }
+}
+
+/// The keyboard layout of the foreground window, which is the input language the user currently types with
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ActiveLayout(HKL);
+
+impl ActiveLayout {
+    pub fn get() -> Self {
+        unsafe {
+            let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), std::ptr::null_mut());
+
+            Self(GetKeyboardLayout(thread_id))
+        }
+    }
+}
+
+impl KeyboardLayout for ActiveLayout {
+    fn virtual_key_of_scan_code(&self, scan_code: u32) -> Option<u32> {
+        let virtual_key = unsafe { MapVirtualKeyExW(scan_code, MAPVK_VSC_TO_VK_EX, self.0) };
+
+        if virtual_key == 0 {
+            None
+        } else {
+            Some(virtual_key)
+        }
+    }
+    fn virtual_key_of_char(&self, c: char) -> Option<(u32, Modifier)> {
+        let mut buffer = [0u16; 2];
+
+        // characters outside of the basic multilingual plane can't be typed with a single key
+        if c.encode_utf16(&mut buffer).len() != 1 {
+            return None;
+        }
+
+        let result = unsafe { VkKeyScanExW(buffer[0], self.0) };
+
+        if result == -1 {
+            return None;
+        }
+
+        let shift_state = (result >> 8) & 0xff;
+        let mut modifier = Modifier::default();
+
+        if shift_state & 1 != 0 {
+            modifier.insert(Modifier::SHIFT);
+        }
+        if shift_state & 2 != 0 {
+            modifier.insert(Modifier::CONTROL);
+        }
+        if shift_state & 4 != 0 {
+            modifier.insert(Modifier::ALT);
+        }
+
+        Some(((result & 0xff) as u32, modifier))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A keyboard layout with the values windows reports for a few keys
+    struct RecordedLayout {
+        /// The scan code and its virtual key, as returned by MapVirtualKeyExW
+        scan_codes: Vec<(u32, u32)>,
+        /// The character, its virtual key and the modifiers to type it, as returned by VkKeyScanExW
+        chars: Vec<(char, u32, Modifier)>,
+    }
+
+    impl KeyboardLayout for RecordedLayout {
+        fn virtual_key_of_scan_code(&self, scan_code: u32) -> Option<u32> {
+            self.scan_codes
+                .iter()
+                .find(|(sc, _)| *sc == scan_code)
+                .map(|(_, vk)| *vk)
+        }
+        fn virtual_key_of_char(&self, c: char) -> Option<(u32, Modifier)> {
+            self.chars
+                .iter()
+                .find(|(ch, _, _)| *ch == c)
+                .map(|(_, vk, modifier)| (*vk, *modifier))
+        }
+    }
+
+    const VK_OEM_PLUS: u32 = 0xBB;
+    const VK_OEM_MINUS: u32 = 0xBD;
+    const VK_OEM_4: u32 = 0xDB;
+    const VK_OEM_6: u32 = 0xDD;
+
+    fn none() -> Modifier {
+        Modifier::default()
+    }
+
+    fn alt_gr() -> Modifier {
+        Modifier::CONTROL | Modifier::ALT
+    }
+
+    /// English (United States), 00000409
+    fn us() -> RecordedLayout {
+        RecordedLayout {
+            scan_codes: vec![
+                (0x0C, VK_OEM_MINUS),
+                (0x0D, VK_OEM_PLUS),
+                (0x10, 'Q' as u32),
+                (0x15, 'Y' as u32),
+                (0x2C, 'Z' as u32),
+            ],
+            chars: vec![
+                ('+', VK_OEM_PLUS, Modifier::SHIFT),
+                ('=', VK_OEM_PLUS, none()),
+                ('-', VK_OEM_MINUS, none()),
+                ('[', VK_OEM_4, none()),
+                ('z', 'Z' as u32, none()),
+            ],
+        }
+    }
+
+    /// German (Germany), 00000407
+    fn de() -> RecordedLayout {
+        RecordedLayout {
+            scan_codes: vec![
+                (0x0C, VK_OEM_4),
+                (0x0D, VK_OEM_6),
+                (0x10, 'Q' as u32),
+                (0x15, 'Z' as u32),
+                (0x1B, VK_OEM_PLUS),
+                (0x2C, 'Y' as u32),
+                (0x35, VK_OEM_MINUS),
+            ],
+            chars: vec![
+                ('+', VK_OEM_PLUS, none()),
+                ('=', '0' as u32, Modifier::SHIFT),
+                ('-', VK_OEM_MINUS, none()),
+                ('[', '8' as u32, alt_gr()),
+                ('ß', VK_OEM_4, none()),
+                ('z', 'Z' as u32, none()),
+            ],
+        }
+    }
+
+    /// French (France), 0000040C
+    fn fr() -> RecordedLayout {
+        RecordedLayout {
+            scan_codes: vec![
+                (0x0C, VK_OEM_4),
+                (0x0D, VK_OEM_PLUS),
+                (0x10, 'A' as u32),
+                (0x11, 'Z' as u32),
+                (0x2C, 'W' as u32),
+            ],
+            chars: vec![
+                ('+', VK_OEM_PLUS, Modifier::SHIFT),
+                ('=', VK_OEM_PLUS, none()),
+                ('-', '6' as u32, none()),
+                ('[', '5' as u32, alt_gr()),
+                ('z', 'Z' as u32, none()),
+            ],
+        }
+    }
+
+    fn new_chord(modifier: Modifier, key: KeySpec) -> Chord {
+        Chord { modifier, key }
+    }
+
+    fn chord_at(scan_code: u32) -> Chord {
+        new_chord(none(), KeySpec::ScanCode(scan_code))
+    }
+
+    fn resolved(modifier: Modifier, virtual_key: u32) -> ResolvedChord {
+        ResolvedChord {
+            modifier,
+            virtual_key,
+        }
+    }
+
+    #[test]
+    fn virtual_keys_stay_the_same_on_every_layout() {
+        let chord = new_chord(Modifier::ALT, KeySpec::Virtual(Key::Plus));
+
+        for layout in [us(), de(), fr()].iter() {
+            assert_eq!(
+                resolve(&chord, layout),
+                Ok(resolved(Modifier::ALT, VK_OEM_PLUS))
+            );
+        }
+    }
+
+    #[test]
+    fn scan_codes_resolve_to_the_key_at_the_position() {
+        let chord = new_chord(Modifier::ALT, KeySpec::ScanCode(0x0C));
+
+        assert_eq!(
+            resolve(&chord, &us()),
+            Ok(resolved(Modifier::ALT, VK_OEM_MINUS))
+        );
+        assert_eq!(
+            resolve(&chord, &de()),
+            Ok(resolved(Modifier::ALT, VK_OEM_4))
+        );
+        assert_eq!(
+            resolve(&chord, &fr()),
+            Ok(resolved(Modifier::ALT, VK_OEM_4))
+        );
+
+        // the letters move too, e.g. QWERTZ and AZERTY
+        let chord = chord_at(0x2C);
+
+        assert_eq!(resolve(&chord, &us()), Ok(resolved(none(), 'Z' as u32)));
+        assert_eq!(resolve(&chord, &de()), Ok(resolved(none(), 'Y' as u32)));
+        assert_eq!(resolve(&chord, &fr()), Ok(resolved(none(), 'W' as u32)));
+    }
+
+    #[test]
+    fn unknown_scan_codes_are_an_error() {
+        let error = resolve(&chord_at(0x7F), &us()).unwrap_err();
+
+        assert!(error.contains("sc:0x7F"), "{}", error);
+    }
+
+    #[test]
+    fn characters_resolve_to_the_key_that_types_them() {
+        let chord = new_chord(Modifier::ALT, KeySpec::Character('-'));
+
+        assert_eq!(
+            resolve(&chord, &us()),
+            Ok(resolved(Modifier::ALT, VK_OEM_MINUS))
+        );
+        assert_eq!(
+            resolve(&chord, &de()),
+            Ok(resolved(Modifier::ALT, VK_OEM_MINUS))
+        );
+        assert_eq!(
+            resolve(&chord, &fr()),
+            Ok(resolved(Modifier::ALT, '6' as u32))
+        );
+    }
+
+    #[test]
+    fn characters_add_the_modifiers_they_need() {
+        let chord = new_chord(Modifier::ALT, KeySpec::Character('+'));
+
+        assert_eq!(
+            resolve(&chord, &us()),
+            Ok(resolved(Modifier::ALT | Modifier::SHIFT, VK_OEM_PLUS))
+        );
+        assert_eq!(
+            resolve(&chord, &de()),
+            Ok(resolved(Modifier::ALT, VK_OEM_PLUS))
+        );
+
+        let chord = new_chord(Modifier::WIN, KeySpec::Character('['));
+
+        assert_eq!(
+            resolve(&chord, &us()),
+            Ok(resolved(Modifier::WIN, VK_OEM_4))
+        );
+        assert_eq!(
+            resolve(&chord, &de()),
+            Ok(resolved(Modifier::WIN | alt_gr(), '8' as u32))
+        );
+        assert_eq!(
+            resolve(&chord, &fr()),
+            Ok(resolved(Modifier::WIN | alt_gr(), '5' as u32))
+        );
+    }
+
+    #[test]
+    fn characters_missing_from_the_layout_are_an_error() {
+        let chord = new_chord(Modifier::ALT, KeySpec::Character('ß'));
+
+        assert_eq!(
+            resolve(&chord, &de()),
+            Ok(resolved(Modifier::ALT, VK_OEM_4))
+        );
+
+        let error = resolve(&chord, &us()).unwrap_err();
+
+        assert!(error.contains("Alt+char:ß"), "{}", error);
+        assert!(resolve(&chord, &fr()).is_err());
+    }
+}
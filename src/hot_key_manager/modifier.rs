@@ -7,5 +7,8 @@ bitflags! {
         const ALT = 0x0001;
         const CONTROL = 0x0002;
         const SHIFT = 0x0004;
+        const WIN = 0x0008;
+        /// Not a real modifier. Tells windows to not repeat the hotkey while it is held down.
+        const NOREPEAT = 0x4000;
     }
 }
@@ -0,0 +1,90 @@
+use super::keyboard_layout::ResolvedChord;
+use super::modifier::Modifier;
+use log::debug;
+use winapi::shared::windef::HWND;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::winuser::RegisterHotKey;
+use winapi::um::winuser::UnregisterHotKey;
+
+/// Applications may only use hotkey ids in this range, the rest is reserved for shared DLLs
+const MAX_ID: i32 = 0xBFFF;
+/// Returned by RegisterHotKey when another application already owns the combination
+const ERROR_HOTKEY_ALREADY_REGISTERED: u32 = 1409;
+
+struct HotKey {
+    chord: ResolvedChord,
+    id: i32,
+    /// How many keybindings and pending sequences use the hotkey
+    users: usize,
+}
+
+/// Owns the hotkeys of the hot key manager thread.
+///
+/// Every chord gets its own id, so two chords can never collide. A chord that is used by more
+/// than one keybinding, e.g. the first chord of sequences that start the same way, gets
+/// registered once and stays registered until the last one releases it.
+#[derive(Default)]
+pub struct Registry {
+    hot_keys: Vec<HotKey>,
+}
+
+impl Registry {
+    fn next_free_id(&self) -> Option<i32> {
+        (1..=MAX_ID).find(|id| !self.hot_keys.iter().any(|hk| hk.id == *id))
+    }
+    /// Registers the chord if nobody uses it yet. Returns the id of the hotkey.
+    pub fn acquire(&mut self, chord: ResolvedChord, repeat: bool) -> Result<i32, String> {
+        if let Some(hk) = self.hot_keys.iter_mut().find(|hk| hk.chord == chord) {
+            hk.users += 1;
+            return Ok(hk.id);
+        }
+
+        let id = self
+            .next_free_id()
+            .ok_or_else(|| "there are no hotkey ids left".to_string())?;
+        let mut flags = chord.modifier;
+
+        if !repeat {
+            flags.insert(Modifier::NOREPEAT);
+        }
+
+        debug!("Registering hotkey {} with id {}", chord, id);
+
+        unsafe {
+            if RegisterHotKey(0 as HWND, id, flags.bits(), chord.virtual_key) == 0 {
+                return Err(match GetLastError() {
+                    ERROR_HOTKEY_ALREADY_REGISTERED => {
+                        format!("{} is already used by another application", chord)
+                    }
+                    code => format!("registering {} failed with error {}", chord, code),
+                });
+            }
+        }
+
+        self.hot_keys.push(HotKey {
+            chord,
+            id,
+            users: 1,
+        });
+
+        Ok(id)
+    }
+    /// Unregisters the chord once nobody uses it anymore
+    pub fn release(&mut self, chord: &ResolvedChord) {
+        if let Some(idx) = self.hot_keys.iter().position(|hk| hk.chord == *chord) {
+            let hk = &mut self.hot_keys[idx];
+
+            hk.users -= 1;
+
+            if hk.users == 0 {
+                debug!("Unregistering hotkey {} with id {}", hk.chord, hk.id);
+
+                unsafe {
+                    UnregisterHotKey(0 as HWND, hk.id);
+                }
+
+                self.hot_keys.remove(idx);
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Step<C> {
+    /// The chords so far are the beginning of at least one sequence. Contains the chords that can follow.
+    Pending(Vec<C>),
+    /// The sequence at the index is complete
+    Matched(usize),
+    /// The chord doesn't continue any sequence
+    Cancelled,
+}
+
+/// Matches key presses against a list of sequences.
+///
+/// This doesn't know anything about hotkeys or the current time, so it can be driven by anything
+/// that produces chords. A sequence that is complete always wins, even if a longer sequence
+/// starts with the same chords. A chord that doesn't continue the started sequence cancels it
+/// and gets treated as the start of a new one.
+pub struct Matcher<C> {
+    sequences: Vec<Vec<C>>,
+    timeout: Duration,
+    pressed: Vec<C>,
+    deadline: Option<Instant>,
+}
+
+impl<C: Copy + PartialEq> Matcher<C> {
+    pub fn new(sequences: Vec<Vec<C>>, timeout: Duration) -> Self {
+        Self {
+            sequences,
+            timeout,
+            pressed: Vec::new(),
+            deadline: None,
+        }
+    }
+    /// The chords of the sequence that got started
+    pub fn pressed(&self) -> &[C] {
+        &self.pressed
+    }
+    pub fn reset(&mut self) {
+        self.pressed.clear();
+        self.deadline = None;
+    }
+    /// Cancels a started sequence once it timed out. Returns whether it got cancelled.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        match self.deadline {
+            Some(deadline) if now >= deadline => {
+                self.reset();
+                true
+            }
+            _ => false,
+        }
+    }
+    pub fn feed(&mut self, chord: C, now: Instant) -> Step<C> {
+        self.tick(now);
+        self.pressed.push(chord);
+
+        let pressed = &self.pressed;
+        let candidates: Vec<usize> = self
+            .sequences
+            .iter()
+            .enumerate()
+            .filter(|(_, sequence)| sequence.starts_with(pressed))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        if candidates.is_empty() {
+            let was_pending = self.pressed.len() > 1;
+
+            self.reset();
+
+            // the chord might be the start of a different sequence
+            if was_pending {
+                return self.feed(chord, now);
+            }
+
+            return Step::Cancelled;
+        }
+
+        if let Some(idx) = candidates
+            .iter()
+            .find(|idx| self.sequences[**idx].len() == self.pressed.len())
+        {
+            let idx = *idx;
+            self.reset();
+            return Step::Matched(idx);
+        }
+
+        self.deadline = Some(now + self.timeout);
+
+        Step::Pending(self.next_chords())
+    }
+    /// The chords that continue the started sequence
+    pub fn next_chords(&self) -> Vec<C> {
+        let mut chords: Vec<C> = Vec::new();
+
+        for sequence in &self.sequences {
+            if sequence.len() > self.pressed.len() && sequence.starts_with(&self.pressed) {
+                let chord = sequence[self.pressed.len()];
+
+                if !chords.contains(&chord) {
+                    chords.push(chord);
+                }
+            }
+        }
+
+        chords
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(1000);
+
+    fn matcher(sequences: &[&str]) -> Matcher<char> {
+        Matcher::new(
+            sequences.iter().map(|s| s.chars().collect()).collect(),
+            TIMEOUT,
+        )
+    }
+
+    #[test]
+    fn matches_a_single_chord() {
+        let mut matcher = matcher(&["a", "b"]);
+        let now = Instant::now();
+
+        assert_eq!(matcher.feed('b', now), Step::Matched(1));
+        assert!(matcher.pressed().is_empty());
+    }
+
+    #[test]
+    fn matches_a_sequence_step_by_step() {
+        let mut matcher = matcher(&["abc"]);
+        let now = Instant::now();
+
+        assert_eq!(matcher.feed('a', now), Step::Pending(vec!['b']));
+        assert_eq!(matcher.feed('b', now), Step::Pending(vec!['c']));
+        assert_eq!(matcher.pressed(), &['a', 'b']);
+        assert_eq!(matcher.feed('c', now), Step::Matched(0));
+        assert!(matcher.pressed().is_empty());
+    }
+
+    #[test]
+    fn shared_prefix_lists_every_next_chord() {
+        let mut matcher = matcher(&["ab", "ac", "abd", "xa"]);
+        let now = Instant::now();
+
+        assert_eq!(matcher.feed('a', now), Step::Pending(vec!['b', 'c']));
+        assert_eq!(matcher.next_chords(), vec!['b', 'c']);
+    }
+
+    #[test]
+    fn complete_sequence_wins_over_a_longer_one() {
+        let mut matcher = matcher(&["abd", "ab"]);
+        let now = Instant::now();
+
+        matcher.feed('a', now);
+
+        assert_eq!(matcher.feed('b', now), Step::Matched(1));
+    }
+
+    #[test]
+    fn wrong_chord_cancels() {
+        let mut matcher = matcher(&["ab"]);
+        let now = Instant::now();
+
+        assert_eq!(matcher.feed('x', now), Step::Cancelled);
+
+        matcher.feed('a', now);
+
+        assert_eq!(matcher.feed('x', now), Step::Cancelled);
+        assert!(matcher.pressed().is_empty());
+    }
+
+    #[test]
+    fn wrong_chord_can_start_another_sequence() {
+        let mut matcher = matcher(&["ab", "x"]);
+        let now = Instant::now();
+
+        matcher.feed('a', now);
+
+        assert_eq!(matcher.feed('x', now), Step::Matched(1));
+    }
+
+    #[test]
+    fn times_out() {
+        let mut matcher = matcher(&["ab"]);
+        let now = Instant::now();
+
+        matcher.feed('a', now);
+
+        assert!(!matcher.tick(now + TIMEOUT / 2));
+        assert_eq!(matcher.pressed(), &['a']);
+        assert!(matcher.tick(now + TIMEOUT));
+        assert!(matcher.pressed().is_empty());
+        assert!(!matcher.tick(now + TIMEOUT * 2));
+    }
+
+    #[test]
+    fn chord_after_the_timeout_starts_over() {
+        let mut matcher = matcher(&["ab"]);
+        let now = Instant::now();
+
+        matcher.feed('a', now);
+
+        assert_eq!(matcher.feed('b', now + TIMEOUT), Step::Cancelled);
+    }
+
+    #[test]
+    fn empty_sequences_never_match() {
+        let mut matcher = matcher(&["", "ab"]);
+        let now = Instant::now();
+
+        assert_eq!(matcher.feed('a', now), Step::Pending(vec!['b']));
+        assert_eq!(matcher.feed('b', now), Step::Matched(1));
+        assert_eq!(matcher.feed('x', now), Step::Cancelled);
+    }
+}
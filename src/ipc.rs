@@ -0,0 +1,275 @@
+use crate::event::Event;
+use crate::CHANNEL;
+use crossbeam_channel::RecvTimeoutError;
+use log::{debug, error, info};
+use protocol::{ErrorKind, Notification, Request, Response};
+use std::io::{BufRead, BufReader, Write};
+use std::time::Duration;
+use transport::{Connection, Listener};
+
+pub mod client;
+pub mod events;
+pub mod protocol;
+pub mod subscription;
+pub mod transport;
+
+pub const PIPE_NAME: &str = r"\\.\pipe\wwm";
+
+/// How often a subscriber that doesn't get any events is checked for a closed connection
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Starts accepting clients in the background
+pub fn start() -> Result<(), Box<dyn std::error::Error>> {
+    let listener = transport::NamedPipeListener::new(PIPE_NAME).map_err(|e| {
+        format!(
+            "can't create {}, is wwm already running? ({})",
+            PIPE_NAME, e
+        )
+    })?;
+
+    std::thread::spawn(move || serve(listener));
+
+    Ok(())
+}
+
+pub fn serve(listener: impl Listener) {
+    loop {
+        match listener.accept() {
+            Ok(stream) => {
+                debug!("IPC client connected");
+                std::thread::spawn(move || handle_client(stream));
+            }
+            Err(e) => {
+                error!("Failed to accept IPC client {}", e);
+                // don't spin if the endpoint is broken for good
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+        }
+    }
+}
+
+/// Answers every line the client sends until it disconnects
+fn handle_client(stream: impl Connection) {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to read from IPC client {}", e);
+                break;
+            }
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Subscribe { events }) => {
+                stream_events(reader.get_mut(), events);
+                break;
+            }
+            Ok(request) => dispatch(request),
+            Err(e) => Response::error(ErrorKind::InvalidRequest, e.to_string()),
+        };
+
+        if let Err(e) = write_response(reader.get_mut(), &response) {
+            error!("Failed to answer IPC client {}", e);
+            break;
+        }
+    }
+
+    debug!("IPC client disconnected");
+}
+
+/// Sends the events to the client until it disconnects
+fn stream_events(stream: &mut impl Connection, events: Vec<String>) {
+    if let Some(unknown) = events.iter().find(|e| !Notification::is_known(e)) {
+        let response = Response::error(
+            ErrorKind::InvalidRequest,
+            format!("unknown event '{}'", unknown),
+        );
+        let _ = write_response(stream, &response);
+        return;
+    }
+
+    info!("IPC client subscribed to {:?}", events);
+
+    let subscription = subscription::subscribe(events);
+
+    if write_response(stream, &Response::ok()).is_err() {
+        return;
+    }
+
+    // dropping the subscription when the client is gone unsubscribes it
+    loop {
+        match subscription.recv_timeout(CONNECTION_CHECK_INTERVAL) {
+            Ok(line) => {
+                if writeln!(stream, "{}", line)
+                    .and_then(|_| stream.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if !stream.is_connected() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn write_response(
+    stream: &mut impl Write,
+    response: &Response,
+) -> Result<(), Box<dyn std::error::Error>> {
+    writeln!(stream, "{}", serde_json::to_string(response)?)?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+/// Lets the main loop answer the request, so it never races with keybindings or win events
+pub fn dispatch(request: Request) -> Response {
+    info!("Received IPC request {:?}", request);
+
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+
+    if CHANNEL
+        .sender
+        .clone()
+        .send(Event::IpcRequest(request, sender))
+        .is_err()
+    {
+        return Response::error(ErrorKind::Unavailable, "wwm is shutting down");
+    }
+
+    receiver
+        .recv()
+        .unwrap_or_else(|_| Response::error(ErrorKind::Unavailable, "wwm is shutting down"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::time::Duration;
+    use transport::{MemoryListener, MemoryStream};
+
+    fn start_server() -> impl Fn() -> MemoryStream {
+        let (listener, connect) = MemoryListener::new();
+
+        std::thread::spawn(move || serve(listener));
+
+        connect
+    }
+
+    fn send(stream: &mut BufReader<MemoryStream>, line: &str) -> Response {
+        writeln!(stream.get_mut(), "{}", line).unwrap();
+
+        let mut answer = String::new();
+        stream.read_line(&mut answer).unwrap();
+
+        serde_json::from_str(&answer).unwrap()
+    }
+
+    #[test]
+    fn malformed_requests_are_invalid() {
+        let connect = start_server();
+        let mut stream = BufReader::new(connect());
+
+        for line in &[
+            "not json",
+            r#"{"type":"shutdown"}"#,
+            r#"{"command":"quit"}"#,
+        ] {
+            let response = send(&mut stream, line);
+
+            assert!(!response.success);
+            assert_eq!(response.error.unwrap().kind, ErrorKind::InvalidRequest);
+        }
+    }
+
+    #[test]
+    fn unknown_events_can_not_be_subscribed() {
+        let connect = start_server();
+        let mut stream = BufReader::new(connect());
+
+        let response = send(
+            &mut stream,
+            r#"{"type":"subscribe","events":["workspace","windows"]}"#,
+        );
+
+        assert_eq!(
+            response.error.unwrap(),
+            protocol::ErrorDetails {
+                kind: ErrorKind::InvalidRequest,
+                message: "unknown event 'windows'".to_string(),
+            }
+        );
+
+        // the server closes the connection afterwards
+        let mut rest = String::new();
+        assert_eq!(stream.read_line(&mut rest).unwrap(), 0);
+    }
+
+    #[test]
+    fn subscribing_gets_confirmed() {
+        let connect = start_server();
+        let mut stream = BufReader::new(connect());
+
+        let response = send(&mut stream, r#"{"type":"subscribe","events":["mode"]}"#);
+
+        assert_eq!(response, Response::ok());
+    }
+
+    #[test]
+    fn requests_get_answered_by_the_main_loop() {
+        let connect = start_server();
+        let mut stream = BufReader::new(connect());
+
+        let main_loop =
+            std::thread::spawn(
+                || match CHANNEL.receiver.recv_timeout(Duration::from_secs(5)) {
+                    Ok(Event::IpcRequest(request, reply)) => {
+                        reply.send(Response::with_data(vec![1, 2])).unwrap();
+                        request
+                    }
+                    other => panic!("expected an IPC request, got {:?}", other),
+                },
+            );
+
+        let response = send(
+            &mut stream,
+            r#"{"type":"run_command","command":"focus left"}"#,
+        );
+
+        assert_eq!(
+            main_loop.join().unwrap(),
+            Request::RunCommand {
+                command: "focus left".to_string()
+            }
+        );
+        assert_eq!(response, Response::with_data(vec![1, 2]));
+    }
+
+    #[test]
+    fn blank_lines_get_skipped() {
+        let connect = start_server();
+        let mut stream = BufReader::new(connect());
+
+        writeln!(stream.get_mut()).unwrap();
+
+        let response = send(&mut stream, "{");
+
+        assert_eq!(response.error.unwrap().kind, ErrorKind::InvalidRequest);
+    }
+}
@@ -0,0 +1,81 @@
+use super::protocol::{Request, Response};
+use crate::util;
+use std::io::{self, BufRead, BufReader, Write};
+use winapi::shared::winerror::ERROR_PIPE_BUSY;
+use winapi::um::fileapi::CreateFileW;
+use winapi::um::fileapi::OPEN_EXISTING;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::namedpipeapi::WaitNamedPipeW;
+use winapi::um::winnt::GENERIC_READ;
+use winapi::um::winnt::GENERIC_WRITE;
+
+type Stream = super::transport::NamedPipe;
+
+fn open() -> io::Result<Stream> {
+    let name = util::to_widestring(super::PIPE_NAME);
+
+    loop {
+        unsafe {
+            let handle = CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                std::ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                std::ptr::null_mut(),
+            );
+
+            if handle != INVALID_HANDLE_VALUE {
+                return Ok(Stream::from_handle(handle));
+            }
+
+            let error = io::Error::last_os_error();
+
+            // every instance is in use, the server creates a new one as soon as it accepted the client
+            if error.raw_os_error() != Some(ERROR_PIPE_BUSY as i32)
+                || WaitNamedPipeW(name.as_ptr(), 2000) == 0
+            {
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// A connection to the running instance
+pub struct Client {
+    reader: BufReader<Stream>,
+}
+
+impl Client {
+    pub fn connect() -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(open()?),
+        })
+    }
+    pub fn request(&mut self, request: &Request) -> Result<Response, Box<dyn std::error::Error>> {
+        let stream = self.reader.get_mut();
+
+        writeln!(stream, "{}", serde_json::to_string(request)?)?;
+        stream.flush()?;
+
+        let line = self.read_line()?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "wwm closed the connection without answering",
+            )
+        })?;
+
+        Ok(serde_json::from_str(&line)?)
+    }
+    /// Returns None once the connection got closed
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(line.trim_end().to_string()))
+    }
+}
@@ -0,0 +1,198 @@
+use super::protocol::Notification;
+use super::subscription;
+use crate::hooks;
+use crate::hot_key_manager;
+use crate::scripting;
+use crate::GRIDS;
+use crate::WORKSPACE_ID;
+use crate::WORK_MODE;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref LAST_STATE: Mutex<Option<State>> = Mutex::new(None);
+}
+
+/// The parts of the state the events are derived from
+#[derive(Clone, PartialEq)]
+struct State {
+    workspace: i32,
+    mode: String,
+    work_mode: bool,
+    workspaces: Vec<WorkspaceState>,
+}
+
+#[derive(Clone, PartialEq)]
+struct WorkspaceState {
+    id: i32,
+    focused_window: Option<i32>,
+    /// The id and title of every tile
+    windows: Vec<(i32, String)>,
+    /// Changes whenever a tile gets placed differently
+    layout: String,
+}
+
+impl State {
+    fn capture() -> Self {
+        let grids = GRIDS.lock().unwrap();
+
+        Self {
+            workspace: *WORKSPACE_ID.lock().unwrap(),
+            mode: hot_key_manager::get_mode(),
+            work_mode: *WORK_MODE.lock().unwrap(),
+            workspaces: grids
+                .iter()
+                .map(|grid| WorkspaceState {
+                    id: grid.id,
+                    focused_window: grid.focused_window_id,
+                    windows: grid
+                        .tiles
+                        .iter()
+                        .map(|t| (t.window.id, t.window.title.clone()))
+                        .collect(),
+                    layout: format!(
+                        "{} {} {} {:?}",
+                        grid.rows,
+                        grid.columns,
+                        grid.fullscreen,
+                        grid.tiles
+                            .iter()
+                            .map(|t| (t.window.id, t.row, t.column, t.split_direction))
+                            .collect::<Vec<_>>()
+                    ),
+                })
+                .collect(),
+        }
+    }
+    fn window_title(&self, id: i32) -> Option<&String> {
+        self.workspaces
+            .iter()
+            .flat_map(|w| w.windows.iter())
+            .find(|(window, _)| *window == id)
+            .map(|(_, title)| title)
+    }
+    fn focused_window(&self) -> Option<(i32, &String)> {
+        let workspace = self.workspaces.iter().find(|w| w.id == self.workspace)?;
+        let id = workspace.focused_window?;
+
+        self.window_title(id).map(|title| (id, title))
+    }
+}
+
+/// Everything that happened between the two states
+fn diff(old: &State, new: &State) -> Vec<Notification> {
+    let mut notifications = Vec::new();
+
+    if old.workspace != new.workspace {
+        notifications.push(Notification::WorkspaceFocused {
+            id: new.workspace,
+            previous: old.workspace,
+        });
+    }
+
+    for workspace in &new.workspaces {
+        let previous = old.workspaces.iter().find(|w| w.id == workspace.id);
+        let old_windows = previous.map(|w| w.windows.len()).unwrap_or(0);
+
+        if old_windows == 0 && !workspace.windows.is_empty() {
+            notifications.push(Notification::WorkspaceCreated { id: workspace.id });
+        }
+
+        for (id, title) in &workspace.windows {
+            match old.window_title(*id) {
+                None => notifications.push(Notification::WindowManaged {
+                    id: *id,
+                    title: title.clone(),
+                    workspace: workspace.id,
+                }),
+                Some(old_title) if old_title != title => {
+                    notifications.push(Notification::TitleChanged {
+                        id: *id,
+                        title: title.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for workspace in &old.workspaces {
+        for (id, _) in &workspace.windows {
+            if new.window_title(*id).is_none() {
+                notifications.push(Notification::WindowUnmanaged {
+                    id: *id,
+                    workspace: workspace.id,
+                });
+            }
+        }
+    }
+
+    for workspace in &new.workspaces {
+        let previous = old.workspaces.iter().find(|w| w.id == workspace.id);
+
+        if previous.map(|w| w.windows.len()).unwrap_or(0) > 0 && workspace.windows.is_empty() {
+            notifications.push(Notification::WorkspaceEmptied { id: workspace.id });
+        }
+
+        if previous.map(|w| &w.layout) != Some(&workspace.layout) {
+            notifications.push(Notification::LayoutChanged {
+                workspace: workspace.id,
+            });
+        }
+    }
+
+    if let Some((id, title)) = new.focused_window() {
+        if old.focused_window().map(|(old_id, _)| old_id) != Some(id) {
+            notifications.push(Notification::WindowFocused {
+                id,
+                title: title.clone(),
+                workspace: new.workspace,
+            });
+        }
+    }
+
+    if old.mode != new.mode {
+        notifications.push(Notification::ModeChanged {
+            mode: new.mode.clone(),
+        });
+    }
+
+    if old.work_mode != new.work_mode {
+        notifications.push(Notification::WorkModeChanged {
+            enabled: new.work_mode,
+        });
+    }
+
+    notifications
+}
+
+/// Compares the state with the one of the last call and sends out what changed.
+/// Gets called by the main loop after every event, so nothing has to announce its changes itself.
+pub fn publish_changes() {
+    let mut last_state = LAST_STATE.lock().unwrap();
+
+    // the state only gets tracked while someone listens
+    if !subscription::has_subscribers() && !hooks::is_enabled() && !scripting::has_callbacks() {
+        *last_state = None;
+        return;
+    }
+
+    let state = State::capture();
+
+    if let Some(old) = last_state.as_ref() {
+        if *old != state {
+            for notification in diff(old, &state) {
+                publish(notification);
+            }
+        }
+    }
+
+    *last_state = Some(state);
+}
+
+/// Hands the event to the subscribers, hooks and scripts. Only needed for events that aren't visible in the state.
+pub fn publish(notification: Notification) {
+    subscription::publish(&notification);
+    hooks::run(&notification);
+    scripting::dispatch(&notification);
+}
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+
+/// A request of a client. Every request is a single line of JSON, e.g.
+/// `{"type":"run_command","command":"focus left"}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    RunCommand {
+        command: String,
+    },
+    GetWorkspaces,
+    GetTree,
+    GetConfig,
+    GetDisplays,
+    /// Keeps the connection open and sends the events as lines of JSON. An empty list means every event.
+    Subscribe {
+        #[serde(default)]
+        events: Vec<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The request isn't valid JSON or has an unknown type
+    InvalidRequest,
+    /// The command couldn't be parsed
+    InvalidCommand,
+    /// The command got parsed, but executing it failed
+    CommandFailed,
+    /// wwm didn't answer the request, e.g. because it is shutting down
+    Unavailable,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorDetails {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+/// The answer to a request, also a single line of JSON
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Response {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Self {
+            success: true,
+            error: None,
+            data: None,
+        }
+    }
+    pub fn with_data(data: impl Serialize) -> Self {
+        match serde_json::to_value(data) {
+            Ok(value) => Self {
+                data: Some(value),
+                ..Self::ok()
+            },
+            Err(e) => Self::error(ErrorKind::Unavailable, e.to_string()),
+        }
+    }
+    pub fn error(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            error: Some(ErrorDetails {
+                kind,
+                message: message.into(),
+            }),
+            data: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkspaceInfo {
+    pub id: i32,
+    /// The hmonitor of the display the workspace lives on
+    pub display: i32,
+    pub visible: bool,
+    pub focused: bool,
+    pub fullscreen: bool,
+    pub windows: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub id: i32,
+    pub title: String,
+    pub class: String,
+    pub row: Option<i32>,
+    pub column: Option<i32>,
+    pub split_direction: String,
+    pub focused: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkspaceTree {
+    #[serde(flatten)]
+    pub workspace: WorkspaceInfo,
+    pub rows: i32,
+    pub columns: i32,
+    pub tiles: Vec<WindowInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DisplayInfo {
+    pub id: i32,
+    pub primary: bool,
+    pub left: i32,
+    pub right: i32,
+    pub top: i32,
+    pub bottom: i32,
+    /// The workspace that is visible on the display, if any
+    pub workspace: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeybindingInfo {
+    pub mode: String,
+    pub key: String,
+    pub command: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConfigInfo {
+    pub work_mode: bool,
+    pub multi_monitor: bool,
+    pub display_app_bar: bool,
+    pub remove_title_bar: bool,
+    pub remove_task_bar: bool,
+    pub margin: i32,
+    pub padding: i32,
+    pub mode: String,
+    pub modes: Vec<String>,
+    pub layouts: Vec<String>,
+    pub keybindings: Vec<KeybindingInfo>,
+}
+
+/// Bumped whenever an event changes in a way that breaks existing subscribers
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Something that happened, sent to the clients that subscribed to it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum Notification {
+    WorkspaceFocused {
+        id: i32,
+        previous: i32,
+    },
+    /// The first window got placed on the workspace
+    WorkspaceCreated {
+        id: i32,
+    },
+    /// The last window left the workspace
+    WorkspaceEmptied {
+        id: i32,
+    },
+    WindowManaged {
+        id: i32,
+        title: String,
+        workspace: i32,
+    },
+    WindowUnmanaged {
+        id: i32,
+        workspace: i32,
+    },
+    WindowFocused {
+        id: i32,
+        title: String,
+        workspace: i32,
+    },
+    TitleChanged {
+        id: i32,
+        title: String,
+    },
+    /// Tiles got added, removed, moved or resized
+    LayoutChanged {
+        workspace: i32,
+    },
+    ModeChanged {
+        mode: String,
+    },
+    WorkModeChanged {
+        enabled: bool,
+    },
+    ConfigReloaded,
+}
+
+impl Notification {
+    pub const NAMES: [&'static str; 11] = [
+        "workspace_focused",
+        "workspace_created",
+        "workspace_emptied",
+        "window_managed",
+        "window_unmanaged",
+        "window_focused",
+        "title_changed",
+        "layout_changed",
+        "mode_changed",
+        "work_mode_changed",
+        "config_reloaded",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WorkspaceFocused { .. } => "workspace_focused",
+            Self::WorkspaceCreated { .. } => "workspace_created",
+            Self::WorkspaceEmptied { .. } => "workspace_emptied",
+            Self::WindowManaged { .. } => "window_managed",
+            Self::WindowUnmanaged { .. } => "window_unmanaged",
+            Self::WindowFocused { .. } => "window_focused",
+            Self::TitleChanged { .. } => "title_changed",
+            Self::LayoutChanged { .. } => "layout_changed",
+            Self::ModeChanged { .. } => "mode_changed",
+            Self::WorkModeChanged { .. } => "work_mode_changed",
+            Self::ConfigReloaded => "config_reloaded",
+        }
+    }
+    /// A filter is either the name of an event or the part before its first underscore, e.g. `workspace`
+    pub fn matches(&self, filter: &str) -> bool {
+        let name = self.name();
+
+        name == filter || name.split('_').next() == Some(filter)
+    }
+    /// Whether a filter can ever match
+    pub fn is_known(filter: &str) -> bool {
+        Self::NAMES
+            .iter()
+            .any(|name| *name == filter || name.split('_').next() == Some(filter))
+    }
+}
+
+/// The line that gets sent for every event
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EventMessage {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub notification: Notification,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requests_are_tagged_with_their_type() {
+        assert_eq!(
+            serde_json::to_value(Request::RunCommand {
+                command: "quit".to_string()
+            })
+            .unwrap(),
+            json!({"type": "run_command", "command": "quit"})
+        );
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"type":"get_workspaces"}"#).unwrap(),
+            Request::GetWorkspaces
+        );
+    }
+
+    #[test]
+    fn subscribe_defaults_to_every_event() {
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"type":"subscribe"}"#).unwrap(),
+            Request::Subscribe { events: Vec::new() }
+        );
+    }
+
+    #[test]
+    fn responses_leave_out_what_they_do_not_have() {
+        assert_eq!(
+            serde_json::to_value(Response::ok()).unwrap(),
+            json!({"success": true})
+        );
+        assert_eq!(
+            serde_json::to_value(Response::error(ErrorKind::CommandFailed, "no window")).unwrap(),
+            json!({
+                "success": false,
+                "error": {"kind": "command_failed", "message": "no window"}
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(Response::with_data(3)).unwrap(),
+            json!({"success": true, "data": 3})
+        );
+    }
+
+    #[test]
+    fn responses_round_trip() {
+        let response = Response::error(ErrorKind::InvalidCommand, "unknown command 'x'");
+        let line = serde_json::to_string(&response).unwrap();
+
+        assert_eq!(serde_json::from_str::<Response>(&line).unwrap(), response);
+    }
+
+    #[test]
+    fn events_carry_their_data_and_the_schema_version() {
+        let message = EventMessage {
+            schema_version: SCHEMA_VERSION,
+            notification: Notification::WorkspaceFocused { id: 2, previous: 1 },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "schema_version": 1,
+                "event": "workspace_focused",
+                "data": {"id": 2, "previous": 1}
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(EventMessage {
+                schema_version: SCHEMA_VERSION,
+                notification: Notification::ConfigReloaded,
+            })
+            .unwrap(),
+            json!({"schema_version": 1, "event": "config_reloaded"})
+        );
+    }
+
+    #[test]
+    fn event_names_match_their_serde_tag() {
+        let notifications = vec![
+            Notification::WorkspaceEmptied { id: 1 },
+            Notification::TitleChanged {
+                id: 1,
+                title: String::new(),
+            },
+            Notification::WorkModeChanged { enabled: true },
+        ];
+
+        for notification in notifications {
+            let value = serde_json::to_value(&notification).unwrap();
+
+            assert_eq!(value["event"], notification.name());
+            assert!(Notification::NAMES.contains(&notification.name()));
+        }
+    }
+
+    #[test]
+    fn filters_match_names_and_prefixes() {
+        let notification = Notification::WindowFocused {
+            id: 1,
+            title: String::new(),
+            workspace: 1,
+        };
+
+        assert!(notification.matches("window_focused"));
+        assert!(notification.matches("window"));
+        assert!(!notification.matches("workspace"));
+        assert!(!notification.matches("focused"));
+        assert!(Notification::is_known("layout"));
+        assert!(!Notification::is_known("windows"));
+    }
+}
@@ -0,0 +1,162 @@
+use super::protocol::{EventMessage, Notification, SCHEMA_VERSION};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use lazy_static::lazy_static;
+use log::{error, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How many events may pile up for a subscriber before it counts as stuck
+const QUEUE_SIZE: usize = 256;
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+}
+
+struct Subscriber {
+    /// An empty list means every event
+    events: Vec<String>,
+    sender: Sender<String>,
+    /// Set once the client is gone, even if it never got an event to notice it
+    closed: Arc<AtomicBool>,
+}
+
+impl Subscriber {
+    fn wants(&self, notification: &Notification) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| notification.matches(e))
+    }
+}
+
+/// The events of one client as lines of JSON. Dropping it ends the subscription.
+pub struct Subscription {
+    receiver: Receiver<String>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Subscription {
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<String, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn has_subscribers() -> bool {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+
+    subscribers.retain(|s| !s.closed.load(Ordering::SeqCst));
+
+    !subscribers.is_empty()
+}
+
+pub fn subscribe(events: Vec<String>) -> Subscription {
+    let (sender, receiver) = bounded(QUEUE_SIZE);
+    let closed = Arc::new(AtomicBool::new(false));
+
+    SUBSCRIBERS.lock().unwrap().push(Subscriber {
+        events,
+        sender,
+        closed: closed.clone(),
+    });
+
+    Subscription { receiver, closed }
+}
+
+/// Sends the event to everyone who subscribed to it without ever waiting on them.
+/// Subscribers that disconnected or can't keep up get dropped, which closes their connection.
+pub fn publish(notification: &Notification) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let message = EventMessage {
+        schema_version: SCHEMA_VERSION,
+        notification: notification.clone(),
+    };
+
+    let line = match serde_json::to_string(&message) {
+        Ok(line) => line,
+        Err(e) => {
+            error!(
+                "Failed to serialize the {} event {}",
+                notification.name(),
+                e
+            );
+            return;
+        }
+    };
+
+    subscribers.retain(|s| {
+        // a client that disconnected has to go even if it doesn't want this event
+        if s.closed.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        if !s.wants(notification) {
+            return true;
+        }
+
+        match s.sender.try_send(line.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!("Dropping an IPC subscriber that doesn't keep up with the events");
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_subscribed(closed: &Arc<AtomicBool>) -> bool {
+        SUBSCRIBERS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|s| Arc::ptr_eq(&s.closed, closed))
+    }
+
+    #[test]
+    fn subscribers_get_the_events_they_asked_for() {
+        let subscription = subscribe(vec!["mode".to_string()]);
+
+        publish(&Notification::ConfigReloaded);
+        publish(&Notification::ModeChanged {
+            mode: "resize".to_string(),
+        });
+
+        let line = subscription
+            .recv_timeout(Duration::from_millis(100))
+            .unwrap();
+
+        assert_eq!(
+            line,
+            r#"{"schema_version":1,"event":"mode_changed","data":{"mode":"resize"}}"#
+        );
+        assert!(subscription
+            .recv_timeout(Duration::from_millis(10))
+            .is_err());
+    }
+
+    #[test]
+    fn closed_subscribers_get_dropped_by_events_they_do_not_want() {
+        let subscription = subscribe(vec!["mode".to_string()]);
+        let closed = subscription.closed.clone();
+
+        assert!(is_subscribed(&closed));
+
+        drop(subscription);
+        publish(&Notification::ConfigReloaded);
+
+        assert!(!is_subscribed(&closed));
+    }
+}
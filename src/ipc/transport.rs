@@ -0,0 +1,307 @@
+use crate::util;
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use winapi::shared::winerror::ERROR_BROKEN_PIPE;
+use winapi::shared::winerror::ERROR_PIPE_CONNECTED;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::FlushFileBuffers;
+use winapi::um::fileapi::ReadFile;
+use winapi::um::fileapi::WriteFile;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::namedpipeapi::ConnectNamedPipe;
+use winapi::um::namedpipeapi::CreateNamedPipeW;
+use winapi::um::namedpipeapi::PeekNamedPipe;
+use winapi::um::winbase::FILE_FLAG_FIRST_PIPE_INSTANCE;
+use winapi::um::winbase::PIPE_ACCESS_DUPLEX;
+use winapi::um::winbase::PIPE_READMODE_BYTE;
+use winapi::um::winbase::PIPE_REJECT_REMOTE_CLIENTS;
+use winapi::um::winbase::PIPE_TYPE_BYTE;
+use winapi::um::winbase::PIPE_UNLIMITED_INSTANCES;
+use winapi::um::winbase::PIPE_WAIT;
+use winapi::um::winnt::HANDLE;
+
+const BUFFER_SIZE: u32 = 4096;
+
+/// A stream to a client
+pub trait Connection: Read + Write + Send + 'static {
+    /// Whether the client is still there, without waiting for it to send something
+    fn is_connected(&mut self) -> bool;
+}
+
+/// Hands out a stream for every client that connects
+pub trait Listener: Send + 'static {
+    type Stream: Connection;
+
+    /// Blocks until the next client connects
+    fn accept(&self) -> io::Result<Self::Stream>;
+}
+
+pub struct NamedPipeListener {
+    name: Vec<u16>,
+    /// The instance that got created to claim the name, it serves the first client
+    first: Cell<Option<NamedPipe>>,
+}
+
+impl NamedPipeListener {
+    /// Fails if another process (e.g. a second wwm) already owns the name
+    pub fn new(name: &str) -> io::Result<Self> {
+        let name = util::to_widestring(name);
+        let first = create_instance(&name, true)?;
+
+        Ok(Self {
+            name,
+            first: Cell::new(Some(first)),
+        })
+    }
+}
+
+/// The pipe can run commands, so it only accepts clients of the same machine
+fn create_instance(name: &[u16], first: bool) -> io::Result<NamedPipe> {
+    let mut open_mode = PIPE_ACCESS_DUPLEX;
+
+    if first {
+        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+
+    unsafe {
+        let handle = CreateNamedPipeW(
+            name.as_ptr(),
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            std::ptr::null_mut(),
+        );
+
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(NamedPipe(handle))
+    }
+}
+
+impl Listener for NamedPipeListener {
+    type Stream = NamedPipe;
+
+    fn accept(&self) -> io::Result<NamedPipe> {
+        // every client gets its own instance of the pipe
+        let pipe = match self.first.take() {
+            Some(pipe) => pipe,
+            None => create_instance(&self.name, false)?,
+        };
+
+        unsafe {
+            // the client might have connected between creating the pipe and waiting for it
+            if ConnectNamedPipe(pipe.0, std::ptr::null_mut()) == 0
+                && GetLastError() != ERROR_PIPE_CONNECTED
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(pipe)
+    }
+}
+
+/// One end of a named pipe connection
+pub struct NamedPipe(HANDLE);
+
+// the handle is only ever used by the thread that owns the pipe
+unsafe impl Send for NamedPipe {}
+
+impl NamedPipe {
+    pub fn from_handle(handle: HANDLE) -> Self {
+        Self(handle)
+    }
+}
+
+impl Connection for NamedPipe {
+    fn is_connected(&mut self) -> bool {
+        unsafe {
+            // fails once the client closed its end
+            PeekNamedPipe(
+                self.0,
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            ) != 0
+        }
+    }
+}
+
+impl Read for NamedPipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+
+        unsafe {
+            if ReadFile(
+                self.0,
+                buf.as_mut_ptr() as *mut _,
+                buf.len() as u32,
+                &mut read,
+                std::ptr::null_mut(),
+            ) == 0
+            {
+                // the other end closed the pipe
+                if GetLastError() == ERROR_BROKEN_PIPE {
+                    return Ok(0);
+                }
+
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(read as usize)
+    }
+}
+
+impl Write for NamedPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        unsafe {
+            if WriteFile(
+                self.0,
+                buf.as_ptr() as *const _,
+                buf.len() as u32,
+                &mut written,
+                std::ptr::null_mut(),
+            ) == 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(written as usize)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        unsafe {
+            if FlushFileBuffers(self.0) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for NamedPipe {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// Connects clients in the same process, so the server can be tested without a named pipe
+#[cfg(test)]
+pub struct MemoryListener {
+    clients: std::sync::mpsc::Receiver<MemoryStream>,
+}
+
+#[cfg(test)]
+impl MemoryListener {
+    /// Returns the listener and a function that connects a new client to it
+    pub fn new() -> (Self, impl Fn() -> MemoryStream) {
+        let (sender, clients) = std::sync::mpsc::channel();
+        let connect = move || {
+            let (client, server) = MemoryStream::pair();
+            sender.send(server).unwrap();
+            client
+        };
+
+        (Self { clients }, connect)
+    }
+}
+
+#[cfg(test)]
+impl Listener for MemoryListener {
+    type Stream = MemoryStream;
+
+    fn accept(&self) -> io::Result<MemoryStream> {
+        self.clients
+            .recv()
+            .map_err(|_| io::Error::new(io::ErrorKind::NotConnected, "no more clients"))
+    }
+}
+
+/// One end of an in-memory connection
+#[cfg(test)]
+pub struct MemoryStream {
+    incoming: std::sync::mpsc::Receiver<Vec<u8>>,
+    outgoing: std::sync::mpsc::Sender<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+#[cfg(test)]
+impl MemoryStream {
+    fn pair() -> (Self, Self) {
+        let (a_sender, a_receiver) = std::sync::mpsc::channel();
+        let (b_sender, b_receiver) = std::sync::mpsc::channel();
+        let a = Self {
+            incoming: a_receiver,
+            outgoing: b_sender,
+            buffer: Vec::new(),
+        };
+        let b = Self {
+            incoming: b_receiver,
+            outgoing: a_sender,
+            buffer: Vec::new(),
+        };
+
+        (a, b)
+    }
+}
+
+#[cfg(test)]
+impl Connection for MemoryStream {
+    fn is_connected(&mut self) -> bool {
+        match self.incoming.try_recv() {
+            Ok(bytes) => {
+                self.buffer.extend(bytes);
+                true
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => true,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.buffer.is_empty() {
+            match self.incoming.recv() {
+                Ok(bytes) => self.buffer = bytes,
+                // the other end closed the connection
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.buffer.len());
+        buf[..len].copy_from_slice(&self.buffer[..len]);
+        self.buffer.drain(..len);
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the other end is closed"))?;
+
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
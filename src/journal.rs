@@ -0,0 +1,288 @@
+use crate::task_bar;
+use crate::util;
+use crate::window::gwl_ex_style::GwlExStyle;
+use crate::window::gwl_style::GwlStyle;
+use crate::window::Window;
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use winapi::shared::windef::HWND;
+use winapi::shared::windef::RECT;
+use winapi::um::winuser::IsWindow;
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+use yaml_rust::YamlEmitter;
+use yaml_rust::YamlLoader;
+
+lazy_static! {
+    static ref JOURNAL: Mutex<Journal> = Mutex::new(Journal::default());
+}
+
+/// The state of a window before wwm touched it
+#[derive(Clone)]
+struct Entry {
+    id: i32,
+    class: String,
+    style: GwlStyle,
+    exstyle: GwlExStyle,
+    rect: RECT,
+    maximized: bool,
+}
+
+#[derive(Default)]
+struct Journal {
+    entries: Vec<Entry>,
+    task_bar_hidden: bool,
+}
+
+fn get_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_default();
+
+    path.push("wwm");
+    path.push("journal.yaml");
+
+    path
+}
+
+fn insert(hash: &mut Hash, key: &str, value: Yaml) {
+    hash.insert(Yaml::String(key.to_string()), value);
+}
+
+fn write(journal: &Journal) {
+    let mut windows = Vec::new();
+
+    for entry in &journal.entries {
+        let mut window = Hash::new();
+
+        insert(&mut window, "id", Yaml::Integer(entry.id as i64));
+        insert(&mut window, "class", Yaml::String(entry.class.clone()));
+        insert(
+            &mut window,
+            "style",
+            Yaml::Integer(entry.style.bits() as i64),
+        );
+        insert(
+            &mut window,
+            "exstyle",
+            Yaml::Integer(entry.exstyle.bits() as i64),
+        );
+        insert(
+            &mut window,
+            "rect",
+            Yaml::Array(
+                [
+                    entry.rect.left,
+                    entry.rect.top,
+                    entry.rect.right,
+                    entry.rect.bottom,
+                ]
+                .iter()
+                .map(|x| Yaml::Integer(*x as i64))
+                .collect(),
+            ),
+        );
+        insert(&mut window, "maximized", Yaml::Boolean(entry.maximized));
+
+        windows.push(Yaml::Hash(window));
+    }
+
+    let mut doc = Hash::new();
+    insert(
+        &mut doc,
+        "task_bar_hidden",
+        Yaml::Boolean(journal.task_bar_hidden),
+    );
+    insert(&mut doc, "windows", Yaml::Array(windows));
+
+    let mut content = String::new();
+
+    if let Err(e) = YamlEmitter::new(&mut content).dump(&Yaml::Hash(doc)) {
+        error!("Failed to serialize the recovery journal {:?}", e);
+        return;
+    }
+
+    if let Err(e) = util::write_atomically(&get_path(), &content) {
+        error!("Failed to write the recovery journal {}", e);
+    }
+}
+
+fn read() -> Result<Journal, Box<dyn std::error::Error>> {
+    let path = get_path();
+    let mut journal = Journal::default();
+
+    if !path.exists() {
+        return Ok(journal);
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let docs = YamlLoader::load_from_str(&content)?;
+
+    let doc = match docs.get(0) {
+        Some(doc) => doc,
+        None => return Ok(journal),
+    };
+
+    journal.task_bar_hidden = doc["task_bar_hidden"].as_bool().unwrap_or(false);
+
+    for window in doc["windows"].as_vec().cloned().unwrap_or_default() {
+        let rect: Vec<i32> = window["rect"]
+            .as_vec()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|x| x.as_i64())
+            .map(|x| x as i32)
+            .collect();
+
+        if rect.len() != 4 {
+            continue;
+        }
+
+        unsafe {
+            journal.entries.push(Entry {
+                id: window["id"].as_i64().unwrap_or_default() as i32,
+                class: window["class"].as_str().unwrap_or_default().to_string(),
+                style: GwlStyle::from_bits_unchecked(
+                    window["style"].as_i64().unwrap_or_default() as i32
+                ),
+                exstyle: GwlExStyle::from_bits_unchecked(
+                    window["exstyle"].as_i64().unwrap_or_default() as i32,
+                ),
+                rect: RECT {
+                    left: rect[0],
+                    top: rect[1],
+                    right: rect[2],
+                    bottom: rect[3],
+                },
+                maximized: window["maximized"].as_bool().unwrap_or(false),
+            });
+        }
+    }
+
+    Ok(journal)
+}
+
+/// Remembers the original state of a window that is about to be modified.
+/// The window has to have its original style, exstyle and rect set already.
+pub fn record(window: &Window) {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    if journal.entries.iter().any(|e| e.id == window.id) {
+        return;
+    }
+
+    debug!("Recording window {} in the journal", window.id);
+
+    journal.entries.push(Entry {
+        id: window.id,
+        class: util::get_class_name_of_window(window.id as HWND).unwrap_or_default(),
+        style: window.original_style,
+        exstyle: window.original_exstyle,
+        rect: window.original_rect,
+        maximized: window.maximized,
+    });
+
+    // windows that got closed in the meantime don't need to be recovered anymore
+    journal
+        .entries
+        .retain(|e| unsafe { IsWindow(e.id as HWND) } != 0);
+
+    write(&journal);
+}
+
+/// Has to be called once a window got reset to its original state.
+pub fn forget(id: i32) {
+    let mut journal = JOURNAL.lock().unwrap();
+    let len = journal.entries.len();
+
+    journal.entries.retain(|e| e.id != id);
+
+    if journal.entries.len() != len {
+        write(&journal);
+    }
+}
+
+pub fn set_task_bar_hidden(hidden: bool) {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    if journal.task_bar_hidden != hidden {
+        journal.task_bar_hidden = hidden;
+        write(&journal);
+    }
+}
+
+/// Restores every window and the taskbar based on the journal on disk.
+///
+/// This doesn't touch any of the global state of wwm, so it is safe to call from a panic hook or
+/// from a different process.
+pub fn recover() -> Result<(), Box<dyn std::error::Error>> {
+    let journal = read()?;
+
+    if journal.entries.is_empty() && !journal.task_bar_hidden {
+        return Ok(());
+    }
+
+    info!(
+        "Recovering {} windows from the journal",
+        journal.entries.len()
+    );
+
+    for entry in journal.entries {
+        let hwnd = entry.id as HWND;
+
+        // the handle might belong to a different window by now
+        if unsafe { IsWindow(hwnd) } == 0
+            || util::get_class_name_of_window(hwnd).unwrap_or_default() != entry.class
+        {
+            debug!("Window {} doesn't exist anymore", entry.id);
+            continue;
+        }
+
+        let window = Window {
+            id: entry.id,
+            style: entry.style,
+            exstyle: entry.exstyle,
+            original_style: entry.style,
+            original_exstyle: entry.exstyle,
+            original_rect: entry.rect,
+            maximized: entry.maximized,
+            ..Window::default()
+        };
+
+        window.update_style();
+        window.update_exstyle();
+        window.show();
+
+        if !entry.exstyle.contains(GwlExStyle::TOPMOST) {
+            window.remove_topmost().ok();
+        }
+
+        if let Err(e) = window.reset_pos() {
+            error!("Failed to restore the position of {} {}", entry.id, e);
+        }
+
+        if window.maximized {
+            window.send_maximize();
+        }
+    }
+
+    if journal.task_bar_hidden {
+        task_bar::show_all();
+    }
+
+    clear();
+
+    Ok(())
+}
+
+/// Removes the journal, because every window is in its original state again.
+pub fn clear() {
+    let path = get_path();
+
+    if path.exists() {
+        if let Err(e) = std::fs::remove_file(path) {
+            error!("Failed to remove the recovery journal {}", e);
+        }
+    }
+}
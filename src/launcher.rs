@@ -0,0 +1,246 @@
+use crate::util;
+use lazy_static::lazy_static;
+use log::{debug, info};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::WAIT_TIMEOUT;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::CreateProcessW;
+use winapi::um::processthreadsapi::GetExitCodeProcess;
+use winapi::um::processthreadsapi::TerminateProcess;
+use winapi::um::processthreadsapi::PROCESS_INFORMATION;
+use winapi::um::processthreadsapi::STARTUPINFOW;
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::CREATE_UNICODE_ENVIRONMENT;
+
+/// How long a launched process has to create its first window before it stops being tracked
+const WINDOW_TIMEOUT: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref SPAWNED: Mutex<Vec<Spawned>> = Mutex::new(Vec::new());
+}
+
+/// A program that should be started and what to do with its window
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LaunchSpec {
+    /// The program, or the whole command line if there are no `args`
+    pub cmd: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    /// Variables that get added to the environment of wwm
+    pub env: Vec<(String, String)>,
+    /// Runs the command line through `cmd.exe /C`, e.g. for builtins like `start`
+    pub shell: bool,
+    /// The workspace the first window of the process gets placed on
+    pub workspace: Option<i32>,
+}
+
+impl LaunchSpec {
+    pub fn from_command_line(cmd: &str) -> Self {
+        Self {
+            cmd: cmd.to_string(),
+            ..Self::default()
+        }
+    }
+    /// The program and its arguments as one command line
+    pub fn program(&self) -> String {
+        if self.args.is_empty() {
+            self.cmd.clone()
+        } else {
+            std::iter::once(&self.cmd)
+                .chain(self.args.iter())
+                .map(|arg| quote_arg(arg))
+                .collect::<Vec<String>>()
+                .join(" ")
+        }
+    }
+    pub fn command_line(&self) -> String {
+        match self.shell {
+            true => format!("cmd.exe /C {}", self.program()),
+            false => self.program(),
+        }
+    }
+}
+
+/// A process that got launched and didn't create a window yet
+#[derive(Debug, Clone)]
+pub struct Spawned {
+    pub pid: u32,
+    pub command_line: String,
+    pub workspace: Option<i32>,
+    started: Instant,
+}
+
+/// Quotes the argument the way `CommandLineToArgvW` reads it back
+pub fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c == ' ' || c == '\t' || c == '"') {
+        return arg.to_string();
+    }
+
+    wrap_in_quotes(arg)
+}
+
+/// Puts the argument in quotes even if `CommandLineToArgvW` doesn't need them
+pub fn wrap_in_quotes(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // the backslashes in front of a quote and the quote itself have to be escaped
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+            }
+        }
+
+        if c != '\\' {
+            quoted.push(c);
+        }
+    }
+
+    // the closing quote mustn't get escaped by trailing backslashes
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// The environment of wwm with the given variables added, as `NAME=value` strings separated by nulls
+fn environment_block(env: &[(String, String)]) -> Vec<u16> {
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| !env.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)))
+        .chain(env.iter().cloned())
+        .collect();
+
+    vars.sort_by_key(|(name, _)| name.to_uppercase());
+
+    vars.iter()
+        .flat_map(|(name, value)| {
+            format!("{}={}\0", name, value)
+                .encode_utf16()
+                .collect::<Vec<u16>>()
+        })
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+/// Creates the process, the caller has to close the handles
+fn create_process(spec: &LaunchSpec) -> Result<PROCESS_INFORMATION, String> {
+    let command_line = spec.command_line();
+
+    debug!("Launching '{}'", command_line);
+
+    let mut si = STARTUPINFOW::default();
+    let mut pi = PROCESS_INFORMATION::default();
+    let mut command_line_wide = util::to_widestring(&command_line);
+    let cwd = spec.cwd.as_ref().map(|cwd| util::to_widestring(cwd));
+    let mut env = if spec.env.is_empty() {
+        None
+    } else {
+        Some(environment_block(&spec.env))
+    };
+
+    si.cb = std::mem::size_of::<STARTUPINFOW>() as u32;
+
+    unsafe {
+        let x = CreateProcessW(
+            std::ptr::null_mut(),
+            command_line_wide.as_mut_ptr(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+            CREATE_UNICODE_ENVIRONMENT,
+            env.as_mut()
+                .map_or(std::ptr::null_mut(), |env| env.as_mut_ptr() as *mut _),
+            cwd.as_ref().map_or(std::ptr::null(), |cwd| cwd.as_ptr()),
+            &mut si,
+            &mut pi,
+        );
+
+        if x != 1 {
+            return Err(format!(
+                "failed to launch '{}': {}",
+                command_line,
+                std::io::Error::from_raw_os_error(GetLastError() as i32)
+            ));
+        }
+    }
+
+    Ok(pi)
+}
+
+/// Starts the program without waiting for it to finish and returns its process id.
+/// The process is remembered until it creates its first window, see [`claim_window`].
+pub fn spawn(spec: &LaunchSpec) -> Result<u32, String> {
+    let pi = create_process(spec)?;
+
+    unsafe {
+        CloseHandle(pi.hProcess);
+        CloseHandle(pi.hThread);
+    }
+
+    SPAWNED.lock().unwrap().push(Spawned {
+        pid: pi.dwProcessId,
+        command_line: spec.command_line(),
+        workspace: spec.workspace,
+        started: Instant::now(),
+    });
+
+    Ok(pi.dwProcessId)
+}
+
+/// Returns the launched process that created the window, if the window is its first one
+pub fn claim_window(hwnd: HWND) -> Option<Spawned> {
+    let mut spawned = SPAWNED.lock().unwrap();
+
+    spawned.retain(|s| s.started.elapsed() < WINDOW_TIMEOUT);
+
+    if spawned.is_empty() {
+        return None;
+    }
+
+    let pid = util::get_process_id_of_window(hwnd).ok()?;
+    let idx = spawned.iter().position(|s| s.pid == pid)?;
+    let process = spawned.remove(idx);
+
+    info!(
+        "'{}' (pid {}) created the window {}",
+        process.command_line, pid, hwnd as i32
+    );
+
+    Some(process)
+}
+
+/// Starts the given command line and waits for it to exit successfully.
+/// The process gets killed if it is still running after the timeout.
+pub fn run(cmd: &str, timeout: Duration) -> Result<(), String> {
+    let pi = create_process(&LaunchSpec::from_command_line(cmd))?;
+
+    unsafe {
+        let result = if WaitForSingleObject(pi.hProcess, timeout.as_millis() as u32) == WAIT_TIMEOUT
+        {
+            TerminateProcess(pi.hProcess, 1);
+            Err(format!("still running after {}ms", timeout.as_millis()))
+        } else {
+            let mut exit_code = 0;
+            GetExitCodeProcess(pi.hProcess, &mut exit_code);
+
+            match exit_code {
+                0 => Ok(()),
+                code => Err(format!("exited with code {}", code)),
+            }
+        };
+
+        CloseHandle(pi.hProcess);
+        CloseHandle(pi.hThread);
+
+        result
+    }
+}
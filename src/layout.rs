@@ -0,0 +1,233 @@
+use crate::change_workspace;
+use crate::config::LayoutNode;
+use crate::config::Slot;
+use crate::event::Event;
+use crate::is_visible_workspace;
+use crate::launcher;
+use crate::launcher::LaunchSpec;
+use crate::tile_grid::TileGrid;
+use crate::util;
+use crate::window::Window;
+use crate::CHANNEL;
+use crate::CONFIG;
+use crate::GRIDS;
+use crate::WORKSPACE_ID;
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use winapi::shared::windef::HWND;
+
+/// How long the placeholder of a slot with a `cmd` waits for the window of the launched program
+const PLACEHOLDER_TIMEOUT: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref PENDING: Mutex<Vec<Pending>> = Mutex::new(Vec::new());
+    static ref NEXT_PLACEHOLDER_ID: Mutex<i32> = Mutex::new(-1);
+}
+
+/// A placeholder that still waits for its window
+struct Pending {
+    placeholder_id: i32,
+    slot: Slot,
+    created: Instant,
+}
+
+fn first_slot(node: &LayoutNode) -> &Slot {
+    match node {
+        LayoutNode::Slot(slot) => slot,
+        LayoutNode::Split(_, children) => first_slot(&children[0]),
+    }
+}
+
+/// Splits the focused tile to make room for a placeholder of the slot.
+fn add_placeholder(grid: &mut TileGrid, slot: &Slot, pending: &mut Vec<Pending>) -> i32 {
+    let mut next_id = NEXT_PLACEHOLDER_ID.lock().unwrap();
+    let id = *next_id;
+
+    *next_id -= 1;
+
+    grid.split(Window {
+        id,
+        title: String::from("Placeholder"),
+        ..Window::default()
+    });
+
+    pending.push(Pending {
+        placeholder_id: id,
+        slot: slot.clone(),
+        created: Instant::now(),
+    });
+
+    id
+}
+
+/// The grid can't nest splits, so every child of a split gets its own row or column first
+/// and only afterwards the children get split themselves.
+///
+/// `first_id` is the placeholder of the first slot in the node, which already exists.
+fn expand(grid: &mut TileGrid, node: &LayoutNode, first_id: i32, pending: &mut Vec<Pending>) {
+    if let LayoutNode::Split(direction, children) = node {
+        let mut ids = vec![first_id];
+
+        for child in children.iter().skip(1) {
+            grid.focused_window_id = ids.last().copied();
+            grid.set_focused_split_direction(*direction);
+            ids.push(add_placeholder(grid, first_slot(child), pending));
+        }
+
+        for (child, id) in children.iter().zip(ids) {
+            expand(grid, child, id, pending);
+        }
+    }
+}
+
+/// Creates the placeholders of the layout and launches the commands of its slots.
+pub fn apply(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let maybe_layout = CONFIG
+        .lock()
+        .unwrap()
+        .layouts
+        .iter()
+        .find(|l| l.name == name)
+        .cloned();
+
+    let layout = match maybe_layout {
+        Some(layout) => layout,
+        None => {
+            error!("Layout '{}' doesn't exist", name);
+            return Ok(());
+        }
+    };
+
+    info!("Applying layout '{}'", name);
+
+    let current_workspace_id = *WORKSPACE_ID.lock().unwrap();
+    let workspace_id = if layout.workspace == -1 {
+        current_workspace_id
+    } else {
+        layout.workspace
+    };
+
+    if workspace_id != current_workspace_id {
+        change_workspace(workspace_id)?;
+    }
+
+    let mut new_pending = Vec::new();
+
+    {
+        let mut grids = GRIDS.lock().unwrap();
+        let grid = grids
+            .iter_mut()
+            .find(|g| g.id == workspace_id)
+            .ok_or("the workspace of a layout has to be between 1 and 10")?;
+        let previously_focused_id = grid.focused_window_id;
+
+        let root_id = add_placeholder(grid, first_slot(&layout.root), &mut new_pending);
+        expand(grid, &layout.root, root_id, &mut new_pending);
+
+        grid.focused_window_id = previously_focused_id.or(Some(root_id));
+        grid.draw_grid();
+    }
+
+    let commands: Vec<(i32, String)> = new_pending
+        .iter()
+        .filter_map(|p| p.slot.cmd.clone().map(|cmd| (p.placeholder_id, cmd)))
+        .collect();
+
+    PENDING.lock().unwrap().extend(new_pending);
+
+    for (placeholder_id, cmd) in &commands {
+        if let Err(e) = launcher::spawn(&LaunchSpec::from_command_line(cmd)) {
+            error!("{}", e);
+            remove_placeholder(*placeholder_id);
+        }
+    }
+
+    if !commands.is_empty() {
+        std::thread::spawn(|| {
+            std::thread::sleep(PLACEHOLDER_TIMEOUT);
+
+            if let Err(e) = CHANNEL.sender.clone().send(Event::ExpirePlaceholders) {
+                error!("Failed to send ExpirePlaceholders event {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Closes the tile of the placeholder and redraws its workspace.
+fn remove_placeholder(placeholder_id: i32) {
+    let mut grids = GRIDS.lock().unwrap();
+
+    PENDING
+        .lock()
+        .unwrap()
+        .retain(|p| p.placeholder_id != placeholder_id);
+
+    if let Some(grid) = grids
+        .iter_mut()
+        .find(|g| g.get_tile_by_id(placeholder_id).is_some())
+    {
+        grid.close_tile_by_window_id(placeholder_id);
+
+        if is_visible_workspace(grid.id) {
+            grid.draw_grid();
+        }
+    }
+}
+
+/// Removes the placeholders of slots with a `cmd` whose program didn't create a matching window in
+/// time, e.g. because it exited right away. Slots without a `cmd` keep waiting.
+pub fn expire_placeholders() {
+    let expired: Vec<i32> = PENDING
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|p| p.slot.cmd.is_some() && p.created.elapsed() >= PLACEHOLDER_TIMEOUT)
+        .map(|p| p.placeholder_id)
+        .collect();
+
+    for placeholder_id in expired {
+        debug!("Placeholder {} timed out", placeholder_id);
+        remove_placeholder(placeholder_id);
+    }
+}
+
+/// Returns the id of the first placeholder whose slot matches the window.
+/// The placeholder doesn't wait for other windows afterwards.
+pub fn take_placeholder(window: &Window) -> Option<i32> {
+    let grids = GRIDS.lock().unwrap();
+    let mut pending = PENDING.lock().unwrap();
+
+    // placeholders that got closed in the meantime don't wait for a window anymore
+    pending.retain(|p| {
+        grids
+            .iter()
+            .any(|g| g.tiles.iter().any(|t| t.window.id == p.placeholder_id))
+    });
+
+    drop(grids);
+
+    if pending.is_empty() {
+        return None;
+    }
+
+    let hwnd = window.id as HWND;
+    let class = util::get_class_name_of_window(hwnd).unwrap_or_default();
+    let process = util::get_process_name_of_window(hwnd).unwrap_or_default();
+
+    let idx = pending
+        .iter()
+        .position(|p| p.slot.matches(&window.title, &class, &process))?;
+
+    let placeholder_id = pending.remove(idx).placeholder_id;
+
+    debug!(
+        "Window '{}' fills placeholder {}",
+        window.title, placeholder_id
+    );
+
+    Some(placeholder_id)
+}
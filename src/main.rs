@@ -5,29 +5,42 @@ extern crate num_derive;
 #[macro_use]
 extern crate strum_macros;
 
-use crate::display::get_display_by_hmonitor;
-use crate::display::get_display_by_idx;
+use crate::display::find_display_by_idx;
 use app_bar::RedrawAppBarReason;
+use config::diff::{ConfigAction, ConfigDiff};
+use config::source::Diagnostic;
 use config::Config;
 use crossbeam_channel::select;
 use display::Display;
 use event::Event;
 use event::EventChannel;
+use ipc::protocol::Notification;
 use lazy_static::lazy_static;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Mutex;
 use tile_grid::TileGrid;
 use winapi::shared::windef::HWND;
 use workspace::Workspace;
 
 mod app_bar;
+mod cli;
+mod command;
 mod config;
 mod display;
 mod event;
 mod event_handler;
+mod history;
+mod hooks;
 mod hot_key_manager;
+mod ipc;
+mod journal;
+mod launcher;
+mod layout;
 mod logging;
+mod scripting;
+mod session;
 mod startup;
 mod task_bar;
 mod tile;
@@ -42,7 +55,7 @@ mod workspace;
 lazy_static! {
     pub static ref WORK_MODE: Mutex<bool> = Mutex::new(CONFIG.lock().unwrap().work_mode);
     pub static ref CONFIG: Mutex<Config> =
-        Mutex::new(config::load().expect("Failed to load config"));
+        Mutex::new(config::load().unwrap_or_else(|e| panic!("Failed to load config: {}", e)));
     pub static ref DISPLAYS: Mutex<Vec<Display>> = Mutex::new(Vec::new());
     pub static ref CHANNEL: EventChannel = EventChannel::default();
     pub static ref GRIDS: Mutex<Vec<TileGrid>> =
@@ -61,12 +74,22 @@ fn unmanage_everything() -> Result<(), util::WinApiResultError> {
             grid.close_tile_by_window_id(tile.window.id);
             tile.window.reset()?;
         }
+        for mut window in grid.floating.drain(..) {
+            window.show();
+            window.reset()?;
+        }
     }
 
     Ok(())
 }
 
 fn on_quit() -> Result<(), util::WinApiResultError> {
+    if CONFIG.lock().unwrap().restore_session && *WORK_MODE.lock().unwrap() {
+        if let Err(e) = session::save() {
+            error!("Failed to save the session {}", e);
+        }
+    }
+
     unmanage_everything()?;
 
     let config = CONFIG.lock().unwrap();
@@ -77,6 +100,8 @@ fn on_quit() -> Result<(), util::WinApiResultError> {
 
     win_event_handler::unregister()?;
 
+    journal::clear();
+
     std::process::exit(0);
 }
 
@@ -100,8 +125,12 @@ pub fn change_workspace(id: i32) -> Result<(), util::WinApiResultError> {
         .map(|(i, g)| (i, g.clone()))
         .unwrap();
 
-    if let Some(setting) = workspace_settings.iter().find(|s| s.id == id) {
-        new_grid.display = get_display_by_idx(setting.monitor);
+    if let Some(display) = workspace_settings
+        .iter()
+        .find(|s| s.id == id)
+        .and_then(|s| find_display_by_idx(s.monitor))
+    {
+        new_grid.display = display;
     }
 
     let mut visible_workspaces = VISIBLE_WORKSPACES.lock().unwrap();
@@ -141,6 +170,11 @@ pub fn change_workspace(id: i32) -> Result<(), util::WinApiResultError> {
 fn run() -> Result<(), Box<dyn std::error::Error>> {
     let receiver = CHANNEL.receiver.clone();
 
+    // a previous instance didn't get the chance to clean up after itself
+    if let Err(e) = journal::recover() {
+        error!("Failed to recover the windows of the last run {}", e);
+    }
+
     info!("Initializing config");
     lazy_static::initialize(&CONFIG);
 
@@ -182,151 +216,370 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 
         info!("Registering windows event handler");
         win_event_handler::register()?;
+
+        event_handler::winevent::show::adopt_existing_windows();
+
+        if CONFIG.lock().unwrap().restore_session {
+            if let Err(e) = session::restore() {
+                error!("Failed to restore the session {}", e);
+            }
+        }
+    }
+
+    if CONFIG.lock().unwrap().restore_session {
+        session::start_autosave();
+    }
+
+    info!("Loading scripts");
+    scripting::load();
+    scripting::hot_reloading::start();
+
+    let autostart = CONFIG.lock().unwrap().autostart.clone();
+
+    if !autostart.is_empty() {
+        info!("Starting autostart programs");
+
+        let failures: Vec<String> = autostart
+            .iter()
+            .filter_map(|spec| launcher::spawn(spec).err())
+            .collect();
+
+        for failure in &failures {
+            error!("{}", failure);
+        }
+
+        if !failures.is_empty() {
+            tray::notify(
+                &format!("{} autostart program(s) failed to start", failures.len()),
+                &failures.join("\n"),
+            );
+        }
     }
 
     info!("Starting hot key manager");
     hot_key_manager::register()?;
 
+    info!("Starting IPC server");
+    if let Err(e) = ipc::start() {
+        error!("Failed to start the IPC server {}", e);
+    }
+
     loop {
         select! {
             recv(receiver) -> maybe_msg => {
                 let msg = maybe_msg.unwrap();
                 match msg {
-                    Event::Keybinding(kb) => event_handler::keybinding::handle(kb)?,
+                    Event::Keybinding(kb) => {
+                        // e.g. the monitor of the keybinding got unplugged
+                        if let Err(e) = event_handler::command::handle(&kb.commands) {
+                            error!("Failed to run the keybinding {}", e);
+                        }
+                    },
+                    Event::Command(commands) => {
+                        if let Err(e) = event_handler::command::handle(&commands) {
+                            error!("Failed to run '{}' {}", commands, e);
+                        }
+                    },
                     Event::RedrawAppBar(reason) => app_bar::redraw(reason),
                     Event::WinEvent(ev) => event_handler::winevent::handle(ev)?,
+                    Event::IpcRequest(request, reply) => {
+                        // the client might have disconnected already
+                        let _ = reply.send(event_handler::ipc::handle(request));
+                    },
                     Event::Exit => {
                         tray::remove_icon(*tray::WINDOW.lock().unwrap() as HWND);
                         on_quit()?;
                         break;
                     },
+                    Event::ReloadScripts => {
+                        info!("Reloading scripts");
+                        scripting::load();
+                    }
+                    Event::ExpirePlaceholders => layout::expire_placeholders(),
                     Event::ReloadConfig => {
                         info!("Reloading Config");
 
-                        hot_key_manager::unregister();
-
-                        let config = CONFIG.lock().unwrap().clone();
-                        let new_config = config::load().expect("Failed to load config");
-                        let work_mode = *WORK_MODE.lock().unwrap();
-                        let mut draw_app_bar = false;
-
-                        if work_mode {
-                            if config.display_app_bar && new_config.display_app_bar {
-                                if config.app_bar_bg != new_config.app_bar_bg
-                                || config.app_bar_font != new_config.app_bar_font
-                                || config.app_bar_font_size != new_config.app_bar_font_size
-                                || config.app_bar_height != new_config.app_bar_height
-                                || config.light_theme != new_config.light_theme {
-                                    app_bar::close();
-                                    draw_app_bar = true;
-                                }
-                            }
-                            else if config.display_app_bar && !new_config.display_app_bar {
-                                app_bar::close();
-
-                                for d in DISPLAYS.lock().unwrap().iter_mut() {
-                                    d.bottom += config.app_bar_height;
-                                }
-
-                                for grid in GRIDS.lock().unwrap().iter_mut() {
-                                    grid.display = get_display_by_hmonitor(grid.display.hmonitor);
-                                }
-
-                            } else if !config.display_app_bar && new_config.display_app_bar {
-                                draw_app_bar = true;
-
-                                for d in DISPLAYS.lock().unwrap().iter_mut() {
-                                    d.bottom -= config.app_bar_height;
-                                }
-
-                                for grid in GRIDS.lock().unwrap().iter_mut() {
-                                    grid.display = get_display_by_hmonitor(grid.display.hmonitor);
-                                }
-                            }
-                            if config.remove_task_bar && !new_config.remove_task_bar {
-                                task_bar::show();
-                            } else if !config.remove_task_bar && new_config.remove_task_bar {
-                                task_bar::hide();
-                            }
+                        // the running config stays active until the new one turned out to be valid
+                        match config::load() {
+                            Ok(new_config) => apply_config(new_config),
+                            Err(e) => on_config_error(e),
                         }
+                    }
+                }
 
-                        if config.remove_title_bar && !new_config.remove_title_bar {
-                            let mut grids = GRIDS.lock().unwrap();
-
-                            for grid in grids.iter_mut() {
-                                for tile in &mut grid.tiles {
-                                    tile.window.reset_style()?;
-                                    tile.window.update_style();
-                                }
-                            }
-                        } else if !config.remove_title_bar && new_config.remove_title_bar {
-                            let mut grids = GRIDS.lock().unwrap();
-
-                            for grid in grids.iter_mut() {
-                                for tile in &mut grid.tiles {
-                                    tile.window.remove_title_bar();
-                                    tile.window.update_style();
-                                }
-                            }
-                        }
+                ipc::events::publish_changes();
+            }
+        }
+    }
 
-                        if config.launch_on_startup != new_config.launch_on_startup {
-                            startup::set_launch_on_startup(new_config.launch_on_startup)?;
-                        }
+    Ok(())
+}
 
-                        *CONFIG.lock().unwrap() = new_config;
+/// Makes the new config the running one and applies what changed. An action that fails doesn't
+/// stop the others, because the new config is already active at that point.
+fn apply_config(new_config: Config) {
+    let config = CONFIG.lock().unwrap().clone();
+    let diff = ConfigDiff::new(&config, &new_config);
+    let actions = diff.actions();
+
+    if diff.changed().is_empty() {
+        info!("No settings changed");
+    } else {
+        info!("Changed settings: {}", diff.changed().join(", "));
+    }
 
-                        if draw_app_bar {
-                            app_bar::create()?;
-                            app_bar::show();
-                        }
+    if actions.contains(&ConfigAction::RegisterKeybindings) {
+        hot_key_manager::unregister();
+    }
 
-                        hot_key_manager::register()?;
+    *CONFIG.lock().unwrap() = new_config;
 
-                        let mut grids = GRIDS.lock().unwrap();
-                        let grid = grids
-                            .iter_mut()
-                            .find(|g| g.id == *WORKSPACE_ID.lock().unwrap())
-                            .unwrap();
+    // the app bar and the task bar only exist in work mode
+    let work_mode = *WORK_MODE.lock().unwrap();
 
-                        grid.draw_grid();
-                    }
+    let mut failed = None;
+
+    for action in actions {
+        debug!("Applying {:?}", action);
+
+        if let Err(e) = apply_config_action(action, work_mode) {
+            match failed {
+                None => failed = Some(format!("failed to apply {:?}: {}", action, e)),
+                Some(_) => error!("Failed to apply {:?} {}", action, e),
+            }
+        }
+    }
+
+    match failed {
+        Some(message) => on_config_error(message.into()),
+        None => app_bar::set_config_error(None),
+    }
+
+    ipc::events::publish(Notification::ConfigReloaded);
+}
+
+fn apply_config_action(
+    action: ConfigAction,
+    work_mode: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        ConfigAction::CloseAppBar if work_mode => app_bar::close(),
+        ConfigAction::ReinitDisplays => reinit_displays()?,
+        ConfigAction::PlaceWorkspaces => place_workspaces()?,
+        ConfigAction::CreateAppBar if work_mode => {
+            app_bar::create()?;
+            app_bar::show();
+        }
+        ConfigAction::ShowTaskBar if work_mode => task_bar::show(),
+        ConfigAction::HideTaskBar if work_mode => task_bar::hide(),
+        ConfigAction::RematchRules => restyle_windows(true),
+        ConfigAction::RestyleWindows => restyle_windows(false),
+        ConfigAction::SetLaunchOnStartup(enabled) => startup::set_launch_on_startup(enabled)?,
+        ConfigAction::RegisterKeybindings => hot_key_manager::register()?,
+        ConfigAction::Relayout => {
+            for grid in GRIDS.lock().unwrap().iter_mut() {
+                if is_visible_workspace(grid.id) {
+                    grid.draw_grid();
                 }
             }
         }
+        _ => {}
     }
 
     Ok(())
 }
 
-fn main() {
-    logging::setup().expect("Failed to setup logging");
+/// Measures the displays again and moves the workspaces of displays that are gone to the primary one
+fn reinit_displays() -> Result<(), util::WinApiResultError> {
+    display::reinit();
 
-    let panic = std::panic::catch_unwind(|| {
-        info!("");
+    let displays = DISPLAYS.lock().unwrap().clone();
+    let primary = display::get_primary_display();
+    let mut grids = GRIDS.lock().unwrap();
+    let mut visible_workspaces = VISIBLE_WORKSPACES.lock().unwrap();
 
-        update::update().expect("Failed to update the program");
+    for grid in grids.iter_mut() {
+        match displays
+            .iter()
+            .find(|d| d.hmonitor == grid.display.hmonitor)
+        {
+            Some(display) => grid.display = *display,
+            None => {
+                if visible_workspaces.get(&grid.display.hmonitor) == Some(&grid.id) {
+                    grid.hide();
+                }
 
-        ctrlc::set_handler(|| {
-            if let Err(e) = on_quit() {
-                error!("Something happend when cleaning up. {}", e);
+                grid.display = primary;
             }
-        })
-        .unwrap();
+        }
+    }
+
+    visible_workspaces.retain(|hmonitor, _| displays.iter().any(|d| d.hmonitor == *hmonitor));
+
+    for display in displays.iter() {
+        visible_workspaces.entry(display.hmonitor).or_insert(0);
+    }
 
-        if let Err(e) = run() {
-            error!("An error occured {:?}", e);
-            if let Err(e) = on_quit() {
-                error!("Something happend when cleaning up. {}", e);
+    drop(visible_workspaces);
+    drop(grids);
+
+    // the focused workspace might have been on a display that is gone
+    change_workspace(*WORKSPACE_ID.lock().unwrap())
+}
+
+/// Moves the workspaces to the monitors of their settings. Settings of missing monitors get ignored.
+fn place_workspaces() -> Result<(), util::WinApiResultError> {
+    let workspace_settings = CONFIG.lock().unwrap().workspace_settings.clone();
+    let mut grids = GRIDS.lock().unwrap();
+    let mut visible_workspaces = VISIBLE_WORKSPACES.lock().unwrap();
+    let mut moved = false;
+
+    for setting in workspace_settings {
+        let display = match find_display_by_idx(setting.monitor) {
+            Some(display) => display,
+            None => continue,
+        };
+
+        if let Some(grid) = grids
+            .iter_mut()
+            .find(|g| g.id == setting.id && g.display.hmonitor != display.hmonitor)
+        {
+            if visible_workspaces.get(&grid.display.hmonitor) == Some(&grid.id) {
+                grid.hide();
+                visible_workspaces.insert(grid.display.hmonitor, 0);
             }
+
+            grid.display = display;
+            moved = true;
+        }
+    }
+
+    drop(visible_workspaces);
+    drop(grids);
+
+    if moved {
+        change_workspace(*WORKSPACE_ID.lock().unwrap())?;
+    }
+
+    Ok(())
+}
+
+/// Applies the frame policies of the tiled windows again. With `rematch` the rules of the
+/// windows get looked up again first and their opacity gets updated.
+fn restyle_windows(rematch: bool) {
+    let config = CONFIG.lock().unwrap().clone();
+
+    for grid in GRIDS.lock().unwrap().iter_mut() {
+        for tile in &mut grid.tiles {
+            if rematch {
+                let old_opacity = tile.window.rule.as_ref().and_then(|r| r.opacity);
+
+                tile.window.rule = config
+                    .rules
+                    .iter()
+                    .find(|r| r.pattern.is_match(&tile.window.title))
+                    .cloned();
+
+                let opacity = tile.window.rule.as_ref().and_then(|r| r.opacity);
+
+                // a window whose rule lost its opacity becomes opaque again
+                if opacity != old_opacity {
+                    if let Err(e) = tile.window.set_opacity(opacity.unwrap_or(1.0)) {
+                        // the frame can still be restyled, e.g. if only the opacity isn't supported
+                        warn!(
+                            "Failed to change the opacity of the window {} {}",
+                            tile.window.id, e
+                        );
+                    }
+                }
+            }
+
+            tile.window.style = tile.window.original_style;
+            tile.window.apply_frame_policy(config.remove_title_bar);
+            tile.window.update_style();
+        }
+    }
+}
+
+/// Tells the user why the config couldn't be reloaded or applied
+fn on_config_error(e: Box<dyn std::error::Error>) {
+    error!("Failed to reload the config: {}", e);
+
+    // the app bar and the balloon have little space, so the directory gets left out
+    let message = match e.downcast_ref::<Diagnostic>() {
+        Some(diagnostic) => diagnostic.short(),
+        None => e.to_string(),
+    };
+
+    tray::notify("Failed to reload the config", &message);
+    app_bar::set_config_error(Some(message));
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    match cli::take_config_path(&mut args) {
+        Ok(Some(path)) => config::set_path(PathBuf::from(path)),
+        Ok(None) => {}
+        Err(code) => std::process::exit(code),
+    }
+
+    // talking to the running instance shouldn't write to its log
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    logging::setup().expect("Failed to setup logging");
+
+    if args.get(0).map(String::as_str) == Some("recover") {
+        if let Err(e) = journal::recover() {
+            error!("Failed to recover the windows {}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    // a panic in another thread only ends that thread, so only the main thread gives up here
+    std::panic::set_hook(Box::new(|info| {
+        let msg = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|x| x.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown"));
+
+        match info.location() {
+            Some(location) => error!("PANIC: {} at {}", msg, location),
+            None => error!("PANIC: {}", msg),
+        }
+
+        if std::thread::current().name() != Some("main") {
+            return;
+        }
+
+        if let Err(e) = journal::recover() {
+            error!("Failed to recover the windows {}", e);
+        }
+
+        std::process::exit(1);
+    }));
+
+    info!("");
+
+    update::update().expect("Failed to update the program");
+
+    ctrlc::set_handler(|| {
+        if let Err(e) = on_quit() {
+            error!("Something happend when cleaning up. {}", e);
         }
-    });
+    })
+    .unwrap();
 
-    if let Err(err) = panic {
-        if let Ok(msg) = err.downcast::<&'static str>() {
-            error!("PANIC: {}", msg);
-        } else {
-            error!("PANIC: unknown");
+    if let Err(e) = run() {
+        error!("An error occured {:?}", e);
+        if let Err(e) = on_quit() {
+            error!("Something happend when cleaning up. {}", e);
         }
     }
 }
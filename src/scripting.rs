@@ -0,0 +1,195 @@
+use crate::ipc::protocol::Notification;
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use rhai::module_resolvers::FileModuleResolver;
+use rhai::{Dynamic, Engine, FnPtr, Map, Scope, AST};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+mod api;
+pub mod hot_reloading;
+
+/// Scripts that run longer than this many operations get stopped, so a loop can't freeze wwm
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+lazy_static! {
+    static ref ENGINE: Engine = create_engine();
+    static ref SCRIPTS: Mutex<Vec<Script>> = Mutex::new(Vec::new());
+    /// The callbacks that got registered while a script was loading
+    static ref PENDING_CALLBACKS: Mutex<Vec<(String, FnPtr)>> = Mutex::new(Vec::new());
+}
+
+struct Script {
+    name: String,
+    ast: AST,
+    /// The event filter and the function that gets called
+    callbacks: Vec<(String, FnPtr)>,
+}
+
+pub fn get_scripts_dir() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_default();
+
+    path.push("wwm");
+    path.push("scripts");
+
+    path
+}
+
+fn create_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_module_resolver(FileModuleResolver::new_with_path(get_scripts_dir()));
+    engine.on_print(|text| info!("[script] {}", text));
+    engine.on_debug(|text, source, pos| {
+        debug!("[script] {} {} {}", source.unwrap_or_default(), pos, text)
+    });
+
+    api::register(&mut engine);
+
+    engine
+}
+
+fn load_script(path: &PathBuf) -> Result<Script, Box<dyn std::error::Error>> {
+    let name = path
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let mut ast = ENGINE.compile(&std::fs::read_to_string(path)?)?;
+
+    ast.set_source(name.as_str());
+    PENDING_CALLBACKS.lock().unwrap().clear();
+    ENGINE.run_ast(&ast)?;
+
+    Ok(Script {
+        name,
+        ast,
+        callbacks: PENDING_CALLBACKS.lock().unwrap().drain(..).collect(),
+    })
+}
+
+/// Loads every `.rhai` file of the scripts folder, replacing the scripts that were loaded before.
+/// The top level statements of a script run once while it gets loaded.
+pub fn load() {
+    let dir = get_scripts_dir();
+
+    if !dir.exists() {
+        debug!("scripts folder doesn't exist yet. Creating the folder");
+
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            error!("Failed to create the scripts folder {}", e);
+        }
+    }
+
+    let mut paths: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "rhai"))
+            .collect(),
+        Err(e) => {
+            error!("Failed to read the scripts folder {}", e);
+            Vec::new()
+        }
+    };
+
+    paths.sort();
+
+    let mut scripts = Vec::new();
+
+    for path in paths {
+        match load_script(&path) {
+            Ok(script) => {
+                info!("Loaded script {}", script.name);
+                scripts.push(script);
+            }
+            Err(e) => error!("Failed to load the script {:?} {}", path, e),
+        }
+    }
+
+    *SCRIPTS.lock().unwrap() = scripts;
+}
+
+/// Calls the function with the given name that one of the scripts defines.
+/// Returns why the function couldn't be called or what went wrong inside of it.
+pub fn call(function: &str) -> Result<(), String> {
+    // a script that runs `script ...` itself would wait for its own lock
+    let scripts = SCRIPTS
+        .try_lock()
+        .map_err(|_| format!("'{}' can't be called from inside another script", function))?;
+
+    let script = scripts
+        .iter()
+        .find(|s| {
+            s.ast
+                .iter_functions()
+                .any(|f| f.name == function && f.params.is_empty())
+        })
+        .ok_or_else(|| format!("none of the scripts defines the function '{}'", function))?;
+
+    ENGINE
+        .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, function, ())
+        .map(|_| ())
+        .map_err(|e| {
+            format!(
+                "the function '{}' of {} failed: {}",
+                function, script.name, e
+            )
+        })
+}
+
+/// Calls the callbacks that got registered for the event
+pub fn dispatch(notification: &Notification) {
+    let scripts = match SCRIPTS.try_lock() {
+        Ok(scripts) => scripts,
+        Err(_) => return,
+    };
+
+    if scripts.iter().all(|s| s.callbacks.is_empty()) {
+        return;
+    }
+
+    let mut event = match rhai::serde::to_dynamic(notification)
+        .map(|value| value.try_cast::<Map>().unwrap_or_default())
+    {
+        Ok(event) => event,
+        Err(e) => {
+            error!(
+                "Failed to pass the {} event to the scripts {}",
+                notification.name(),
+                e
+            );
+            return;
+        }
+    };
+
+    // the fields of the event are easier to reach without the nesting
+    if let Some(data) = event.remove("data").and_then(|d| d.try_cast::<Map>()) {
+        event.extend(data);
+    }
+
+    for script in scripts.iter() {
+        for (filter, callback) in &script.callbacks {
+            if filter != "*" && !notification.matches(filter) {
+                continue;
+            }
+
+            if let Err(e) = callback.call::<Dynamic>(&ENGINE, &script.ast, (event.clone(),)) {
+                error!(
+                    "The {} callback of {} failed {}",
+                    notification.name(),
+                    script.name,
+                    e
+                );
+            }
+        }
+    }
+}
+
+pub fn has_callbacks() -> bool {
+    SCRIPTS
+        .try_lock()
+        .map(|scripts| scripts.iter().any(|s| !s.callbacks.is_empty()))
+        .unwrap_or(true)
+}
@@ -0,0 +1,109 @@
+use super::PENDING_CALLBACKS;
+use crate::change_workspace;
+use crate::command::CommandList;
+use crate::event_handler::command;
+use crate::event_handler::ipc::{get_tree, get_workspaces};
+use crate::ipc::protocol::Notification;
+use crate::GRIDS;
+use crate::WORKSPACE_ID;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, Map};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+fn to_dynamic(value: impl serde::Serialize) -> ScriptResult<Dynamic> {
+    rhai::serde::to_dynamic(value)
+}
+
+/// Every managed window together with the id of its workspace
+fn windows() -> ScriptResult<Array> {
+    let mut windows = Array::new();
+
+    for workspace in get_tree() {
+        for tile in workspace.tiles {
+            let mut window = to_dynamic(tile)?.try_cast::<Map>().unwrap_or_default();
+
+            window.insert("workspace".into(), (workspace.workspace.id as i64).into());
+            windows.push(window.into());
+        }
+    }
+
+    Ok(windows)
+}
+
+fn workspaces() -> ScriptResult<Array> {
+    get_workspaces().into_iter().map(to_dynamic).collect()
+}
+
+fn focused_workspace() -> i64 {
+    *WORKSPACE_ID.lock().unwrap() as i64
+}
+
+/// The focused window of the focused workspace or `()`
+fn focused_window() -> ScriptResult<Dynamic> {
+    let windows = windows()?;
+    let workspace = focused_workspace();
+
+    Ok(windows
+        .into_iter()
+        .find(|w| {
+            w.clone().try_cast::<Map>().map_or(false, |w| {
+                w["focused"].as_bool() == Ok(true) && w["workspace"].as_int() == Ok(workspace)
+            })
+        })
+        .unwrap_or(Dynamic::UNIT))
+}
+
+/// Runs commands like a keybinding does, e.g. `run("focus left; toggle floating")`
+fn run(text: &str) -> ScriptResult<()> {
+    let commands = text.parse::<CommandList>()?;
+
+    command::handle(&commands).map_err(|e| e.to_string().into())
+}
+
+/// Focuses a managed window, changing to its workspace if needed
+fn focus_window(id: i64) -> ScriptResult<()> {
+    let id = id as i32;
+    let (workspace, window) = {
+        let mut grids = GRIDS.lock().unwrap();
+        let grid = grids
+            .iter_mut()
+            .find(|g| g.get_tile_by_id(id).is_some())
+            .ok_or(format!("window {} isn't managed", id))?;
+
+        grid.focus_stack.clear();
+        grid.focused_window_id = Some(id);
+
+        (grid.id, grid.get_tile_by_id(id).unwrap().window)
+    };
+
+    if workspace != *WORKSPACE_ID.lock().unwrap() {
+        change_workspace(workspace).map_err(|e| e.to_string())?;
+    }
+
+    window.focus().map_err(|e| e.to_string().into())
+}
+
+/// Calls the function whenever the event happens. `*` stands for every event.
+fn on(event: &str, callback: FnPtr) -> ScriptResult<()> {
+    if event != "*" && !Notification::is_known(event) {
+        return Err(format!("unknown event '{}'", event).into());
+    }
+
+    PENDING_CALLBACKS
+        .lock()
+        .unwrap()
+        .push((event.to_string(), callback));
+
+    Ok(())
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_fn("windows", windows)
+        .register_fn("workspaces", workspaces)
+        .register_fn("focused_workspace", focused_workspace)
+        .register_fn("focused_window", focused_window)
+        .register_fn("run", run)
+        .register_fn("focus_window", focus_window)
+        .register_fn("on", on);
+}
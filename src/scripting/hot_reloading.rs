@@ -0,0 +1,50 @@
+use super::get_scripts_dir;
+use crate::event::Event;
+use crate::CHANNEL;
+use log::{debug, error};
+use notify::watcher;
+use notify::DebouncedEvent;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::sync::mpsc::channel;
+
+pub fn start() {
+    std::thread::spawn(|| {
+        let (tx, rx) = channel();
+
+        let mut watcher = match watcher(tx, std::time::Duration::from_millis(10)) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                error!("Failed to spawn the file watcher of the scripts {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = watcher.watch(get_scripts_dir(), RecursiveMode::NonRecursive) {
+            error!("Failed to watch the scripts directory {}", e);
+            return;
+        }
+
+        loop {
+            match rx.recv() {
+                Ok(ev) => match ev {
+                    DebouncedEvent::Write(_)
+                    | DebouncedEvent::Create(_)
+                    | DebouncedEvent::Remove(_)
+                    | DebouncedEvent::Rename(_, _) => {
+                        debug!("detected script change");
+                        if let Err(e) = CHANNEL.sender.clone().send(Event::ReloadScripts) {
+                            error!("Failed to send ReloadScripts event {}", e);
+                            return;
+                        }
+                    }
+                    _ => {}
+                },
+                Err(e) => {
+                    error!("The file watcher of the scripts stopped {:?}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,521 @@
+use crate::is_visible_workspace;
+use crate::tile::Tile;
+use crate::tile_grid::SplitDirection;
+use crate::util;
+use crate::window::Window;
+use crate::GRIDS;
+use crate::WORKSPACE_ID;
+use crate::WORK_MODE;
+use log::{debug, error, info, warn};
+use std::path::PathBuf;
+use std::str::FromStr;
+use winapi::shared::windef::HWND;
+use winapi::shared::windef::RECT;
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+use yaml_rust::YamlEmitter;
+use yaml_rust::YamlLoader;
+
+const AUTOSAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// What is needed to find a window again after wwm restarted.
+#[derive(Debug, Clone, PartialEq)]
+struct Identity {
+    process: String,
+    class: String,
+    title: String,
+}
+
+impl Identity {
+    fn of(window: &Window) -> Self {
+        let hwnd = window.id as HWND;
+
+        Self {
+            process: util::get_process_name_of_window(hwnd).unwrap_or_default(),
+            class: util::get_class_name_of_window(hwnd).unwrap_or_default(),
+            title: util::get_title_of_window(hwnd).unwrap_or_else(|_| window.title.clone()),
+        }
+    }
+    /// A loose match ignores the title, because most programs show the current document in there.
+    fn matches(&self, other: &Identity, exact: bool) -> bool {
+        self.process == other.process
+            && self.class == other.class
+            && (!exact || self.title == other.title)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SavedTile {
+    identity: Identity,
+    row: Option<i32>,
+    column: Option<i32>,
+    split_direction: SplitDirection,
+}
+
+#[derive(Debug, Clone)]
+struct SavedGrid {
+    id: i32,
+    rows: i32,
+    columns: i32,
+    fullscreen: bool,
+    focused: Option<usize>,
+    tiles: Vec<SavedTile>,
+    floating: Vec<SavedFloat>,
+}
+
+#[derive(Debug, Clone)]
+struct SavedFloat {
+    identity: Identity,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl SavedFloat {
+    fn rect(&self) -> RECT {
+        RECT {
+            left: self.x,
+            top: self.y,
+            right: self.x + self.width,
+            bottom: self.y + self.height,
+        }
+    }
+}
+
+fn get_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_default();
+
+    path.push("wwm");
+    path.push("session.yaml");
+
+    path
+}
+
+fn insert(hash: &mut Hash, key: &str, value: Yaml) {
+    hash.insert(Yaml::String(key.to_string()), value);
+}
+
+fn optional_integer(value: Option<i32>) -> Yaml {
+    value.map(|x| Yaml::Integer(x as i64)).unwrap_or(Yaml::Null)
+}
+
+pub fn save() -> Result<(), Box<dyn std::error::Error>> {
+    let grids = GRIDS.lock().unwrap();
+    let mut workspaces = Vec::new();
+
+    for grid in grids
+        .iter()
+        .filter(|g| !g.tiles.is_empty() || !g.floating.is_empty())
+    {
+        let mut workspace = Hash::new();
+        let mut tiles = Vec::new();
+        let mut floating = Vec::new();
+
+        for tile in &grid.tiles {
+            let identity = Identity::of(&tile.window);
+            let mut saved_tile = Hash::new();
+
+            insert(&mut saved_tile, "process", Yaml::String(identity.process));
+            insert(&mut saved_tile, "class", Yaml::String(identity.class));
+            insert(&mut saved_tile, "title", Yaml::String(identity.title));
+            insert(&mut saved_tile, "row", optional_integer(tile.row));
+            insert(&mut saved_tile, "column", optional_integer(tile.column));
+            insert(
+                &mut saved_tile,
+                "split_direction",
+                Yaml::String(format!("{:?}", tile.split_direction)),
+            );
+
+            tiles.push(Yaml::Hash(saved_tile));
+        }
+
+        for window in &grid.floating {
+            // the window might have been closed since it got managed
+            let rect = match window.get_rect() {
+                Ok(rect) => rect,
+                Err(_) => continue,
+            };
+            let identity = Identity::of(window);
+            let mut saved_float = Hash::new();
+
+            insert(&mut saved_float, "process", Yaml::String(identity.process));
+            insert(&mut saved_float, "class", Yaml::String(identity.class));
+            insert(&mut saved_float, "title", Yaml::String(identity.title));
+            insert(&mut saved_float, "x", Yaml::Integer(rect.left as i64));
+            insert(&mut saved_float, "y", Yaml::Integer(rect.top as i64));
+            insert(
+                &mut saved_float,
+                "width",
+                Yaml::Integer((rect.right - rect.left) as i64),
+            );
+            insert(
+                &mut saved_float,
+                "height",
+                Yaml::Integer((rect.bottom - rect.top) as i64),
+            );
+
+            floating.push(Yaml::Hash(saved_float));
+        }
+
+        let focused = grid
+            .focused_window_id
+            .and_then(|id| grid.tiles.iter().position(|t| t.window.id == id))
+            .map(|idx| idx as i32);
+
+        insert(&mut workspace, "id", Yaml::Integer(grid.id as i64));
+        insert(&mut workspace, "rows", Yaml::Integer(grid.rows as i64));
+        insert(
+            &mut workspace,
+            "columns",
+            Yaml::Integer(grid.columns as i64),
+        );
+        insert(&mut workspace, "fullscreen", Yaml::Boolean(grid.fullscreen));
+        insert(&mut workspace, "focused", optional_integer(focused));
+        insert(&mut workspace, "tiles", Yaml::Array(tiles));
+        insert(&mut workspace, "floating", Yaml::Array(floating));
+
+        workspaces.push(Yaml::Hash(workspace));
+    }
+
+    drop(grids);
+
+    let mut session = Hash::new();
+    insert(&mut session, "workspaces", Yaml::Array(workspaces));
+
+    let mut content = String::new();
+    YamlEmitter::new(&mut content).dump(&Yaml::Hash(session))?;
+
+    let path = get_path();
+
+    util::write_atomically(&path, &content)?;
+
+    debug!("Saved session to {:?}", path);
+
+    Ok(())
+}
+
+fn load() -> Result<Vec<SavedGrid>, Box<dyn std::error::Error>> {
+    let path = get_path();
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let docs = YamlLoader::load_from_str(&content)?;
+    let mut saved_grids = Vec::new();
+
+    let workspaces = match docs.get(0).and_then(|doc| doc["workspaces"].as_vec()) {
+        Some(workspaces) => workspaces,
+        None => return Ok(saved_grids),
+    };
+
+    for workspace in workspaces {
+        let id = workspace["id"].as_i64().ok_or("a workspace needs an id")? as i32;
+
+        if id < 1 || id > 10 {
+            continue;
+        }
+
+        let mut tiles = Vec::new();
+
+        for tile in workspace["tiles"].as_vec().cloned().unwrap_or_default() {
+            tiles.push(SavedTile {
+                identity: Identity {
+                    process: tile["process"].as_str().unwrap_or_default().to_string(),
+                    class: tile["class"].as_str().unwrap_or_default().to_string(),
+                    title: tile["title"].as_str().unwrap_or_default().to_string(),
+                },
+                row: tile["row"].as_i64().map(|x| x as i32),
+                column: tile["column"].as_i64().map(|x| x as i32),
+                split_direction: tile["split_direction"]
+                    .as_str()
+                    .and_then(|x| SplitDirection::from_str(x).ok())
+                    .unwrap_or(SplitDirection::Vertical),
+            });
+        }
+
+        let mut floating = Vec::new();
+
+        for float in workspace["floating"].as_vec().cloned().unwrap_or_default() {
+            let (x, y) = (float["x"].as_i64(), float["y"].as_i64());
+            let (width, height) = (float["width"].as_i64(), float["height"].as_i64());
+
+            if let (Some(x), Some(y), Some(width), Some(height)) = (x, y, width, height) {
+                floating.push(SavedFloat {
+                    identity: Identity {
+                        process: float["process"].as_str().unwrap_or_default().to_string(),
+                        class: float["class"].as_str().unwrap_or_default().to_string(),
+                        title: float["title"].as_str().unwrap_or_default().to_string(),
+                    },
+                    x: x as i32,
+                    y: y as i32,
+                    width: width as i32,
+                    height: height as i32,
+                });
+            }
+        }
+
+        saved_grids.push(SavedGrid {
+            id,
+            rows: workspace["rows"].as_i64().unwrap_or(1) as i32,
+            columns: workspace["columns"].as_i64().unwrap_or(1) as i32,
+            fullscreen: workspace["fullscreen"].as_bool().unwrap_or(false),
+            focused: workspace["focused"].as_i64().map(|x| x as usize),
+            tiles,
+            floating,
+        });
+    }
+
+    Ok(saved_grids)
+}
+
+/// Takes the best match out of the pool for every wanted identity.
+/// Exact matches have to win over loose ones, so they get their own pass.
+fn take_matches<T>(wanted: &[&Identity], pool: &mut [Option<(T, Identity)>]) -> Vec<Option<T>> {
+    let mut matches: Vec<Option<T>> = wanted.iter().map(|_| None).collect();
+
+    for exact in &[true, false] {
+        for (wanted_idx, wanted_identity) in wanted.iter().enumerate() {
+            if matches[wanted_idx].is_some() {
+                continue;
+            }
+
+            let maybe_idx = pool.iter().position(|entry| {
+                entry.as_ref().map_or(false, |(_, identity)| {
+                    wanted_identity.matches(identity, *exact)
+                })
+            });
+
+            if let Some(idx) = maybe_idx {
+                matches[wanted_idx] = pool[idx].take().map(|(value, _)| value);
+            }
+        }
+    }
+
+    matches
+}
+
+/// Rebuilds the layout of the last session with the windows that are currently managed.
+///
+/// Windows of the last session that don't exist anymore get dropped and the grid closes the gap
+/// the same way it does when a window gets closed. Managed windows that are not part of the last
+/// session stay on the workspace they would normally end up on.
+pub fn restore() -> Result<(), Box<dyn std::error::Error>> {
+    let saved_grids = load()?;
+
+    if saved_grids.is_empty() {
+        return Ok(());
+    }
+
+    info!("Restoring session");
+
+    let mut grids = GRIDS.lock().unwrap();
+    let mut pool: Vec<Option<(Tile, Identity)>> = Vec::new();
+    let mut float_pool: Vec<Option<((i32, Window), Identity)>> = Vec::new();
+
+    for grid in grids.iter_mut() {
+        for tile in grid.tiles.drain(..) {
+            let identity = Identity::of(&tile.window);
+            pool.push(Some((tile, identity)));
+        }
+
+        for window in grid.floating.drain(..) {
+            let identity = Identity::of(&window);
+            float_pool.push(Some(((grid.id, window), identity)));
+        }
+
+        grid.rows = 0;
+        grid.columns = 0;
+        grid.fullscreen = false;
+        grid.focused_window_id = None;
+        grid.focus_stack.clear();
+    }
+
+    let wanted_tiles: Vec<&Identity> = saved_grids
+        .iter()
+        .flat_map(|g| g.tiles.iter().map(|t| &t.identity))
+        .collect();
+    let mut assigned_tiles = take_matches(&wanted_tiles, &mut pool).into_iter();
+
+    for saved_grid in &saved_grids {
+        let grid = grids.iter_mut().find(|g| g.id == saved_grid.id).unwrap();
+        let mut placeholder_id = 0;
+
+        grid.rows = saved_grid.rows;
+        grid.columns = saved_grid.columns;
+
+        for saved_tile in &saved_grid.tiles {
+            let window = match assigned_tiles.next().flatten() {
+                Some(tile) => tile.window,
+                None => {
+                    placeholder_id -= 1;
+                    Window {
+                        id: placeholder_id,
+                        ..Window::default()
+                    }
+                }
+            };
+
+            grid.tiles.push(Tile {
+                row: saved_tile.row,
+                column: saved_tile.column,
+                split_direction: saved_tile.split_direction,
+                window,
+            });
+        }
+
+        grid.focused_window_id = saved_grid
+            .focused
+            .and_then(|idx| grid.tiles.get(idx))
+            .map(|t| t.window.id);
+
+        for id in placeholder_id..0 {
+            grid.close_tile_by_window_id(id);
+        }
+
+        if grid.tiles.is_empty() {
+            grid.rows = 0;
+            grid.columns = 0;
+        } else if grid.get_focused_tile().is_none() {
+            grid.focused_window_id = Some(grid.tiles[0].window.id);
+        }
+
+        grid.fullscreen = saved_grid.fullscreen && !grid.tiles.is_empty();
+
+        debug!(
+            "Restored {} tiles on workspace {}",
+            grid.tiles.len(),
+            grid.id
+        );
+    }
+
+    let wanted_floats: Vec<&Identity> = saved_grids
+        .iter()
+        .flat_map(|g| g.floating.iter().map(|f| &f.identity))
+        .collect();
+    let mut assigned_floats = take_matches(&wanted_floats, &mut float_pool).into_iter();
+
+    for saved_grid in &saved_grids {
+        let grid = grids.iter_mut().find(|g| g.id == saved_grid.id).unwrap();
+
+        for saved_float in &saved_grid.floating {
+            if let Some((_, window)) = assigned_floats.next().flatten() {
+                if let Err(e) = window.set_rect(saved_float.rect()) {
+                    warn!("Failed to restore the position of '{}' {}", window.title, e);
+                }
+
+                grid.floating.push(window);
+            }
+        }
+    }
+
+    // floating windows that are not part of the last session stay where they are
+    for (grid_id, window) in float_pool.into_iter().flatten().map(|(entry, _)| entry) {
+        if let Some(grid) = grids.iter_mut().find(|g| g.id == grid_id) {
+            grid.floating.push(window);
+        }
+    }
+
+    let current_workspace_id = *WORKSPACE_ID.lock().unwrap();
+
+    for (tile, _) in pool.into_iter().flatten() {
+        let workspace_id = tile
+            .window
+            .rule
+            .as_ref()
+            .map(|r| r.workspace)
+            .filter(|id| *id != -1)
+            .unwrap_or(current_workspace_id);
+
+        if let Some(grid) = grids.iter_mut().find(|g| g.id == workspace_id) {
+            grid.split(tile.window);
+        }
+    }
+
+    for grid in grids.iter() {
+        if is_visible_workspace(grid.id) {
+            grid.show();
+            grid.draw_grid();
+        } else {
+            grid.hide();
+        }
+    }
+
+    Ok(())
+}
+
+pub fn start_autosave() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(AUTOSAVE_INTERVAL);
+
+        if *WORK_MODE.lock().unwrap() {
+            if let Err(e) = save() {
+                error!("Failed to save the session {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(process: &str, title: &str) -> Identity {
+        Identity {
+            process: process.to_string(),
+            class: "Window".to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn exact_matches_win_over_loose_ones() {
+        let first = identity("code.exe", "a.rs");
+        let second = identity("code.exe", "b.rs");
+        let mut pool = vec![
+            Some((1, identity("code.exe", "b.rs"))),
+            Some((2, identity("code.exe", "a.rs"))),
+        ];
+
+        let matches = take_matches(&[&first, &second], &mut pool);
+
+        assert_eq!(matches, vec![Some(2), Some(1)]);
+        assert!(pool.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn loose_matches_ignore_the_title() {
+        let wanted = identity("code.exe", "a.rs");
+        let mut pool = vec![Some((1, identity("code.exe", "c.rs")))];
+
+        assert_eq!(take_matches(&[&wanted], &mut pool), vec![Some(1)]);
+    }
+
+    #[test]
+    fn unmatched_windows_stay_in_the_pool() {
+        let wanted = identity("code.exe", "a.rs");
+        let mut pool = vec![Some((1, identity("firefox.exe", "a.rs")))];
+
+        assert_eq!(take_matches(&[&wanted], &mut pool), vec![None]);
+        assert!(pool[0].is_some());
+    }
+
+    #[test]
+    fn saved_floats_keep_their_size() {
+        let float = SavedFloat {
+            identity: identity("calc.exe", "Calculator"),
+            x: -100,
+            y: 50,
+            width: 300,
+            height: 400,
+        };
+        let rect = float.rect();
+
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (-100, 50, 200, 450)
+        );
+    }
+}
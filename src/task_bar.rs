@@ -1,3 +1,4 @@
+use crate::journal;
 use crate::DISPLAYS;
 use lazy_static::lazy_static;
 use log::debug;
@@ -7,6 +8,7 @@ use std::sync::Mutex;
 use winapi::shared::windef::HWND;
 use winapi::shared::windef::RECT;
 use winapi::um::winuser::FindWindowA;
+use winapi::um::winuser::FindWindowExA;
 use winapi::um::winuser::GetWindowRect;
 use winapi::um::winuser::ShowWindow;
 use winapi::um::winuser::SW_HIDE;
@@ -49,6 +51,7 @@ pub fn init() {
 
 pub fn show() {
     debug!("Showing taskbar");
+    journal::set_task_bar_hidden(false);
     let hwnds: Vec<i32> = WINDOWS
         .lock()
         .unwrap()
@@ -65,6 +68,7 @@ pub fn show() {
 
 pub fn hide() {
     debug!("Hiding taskbar");
+    journal::set_task_bar_hidden(true);
     let hwnds: Vec<i32> = WINDOWS
         .lock()
         .unwrap()
@@ -78,3 +82,32 @@ pub fn hide() {
         }
     }
 }
+
+/// Shows the taskbar of every monitor without relying on the displays that wwm knows about.
+pub fn show_all() {
+    debug!("Showing every taskbar");
+
+    let primary = CString::new("Shell_TrayWnd").unwrap();
+    let secondary = CString::new("Shell_SecondaryTrayWnd").unwrap();
+
+    unsafe {
+        ShowWindow(FindWindowA(primary.as_ptr(), std::ptr::null()), SW_SHOW);
+
+        let mut hwnd = FindWindowExA(
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            secondary.as_ptr(),
+            std::ptr::null(),
+        );
+
+        while !hwnd.is_null() {
+            ShowWindow(hwnd, SW_SHOW);
+            hwnd = FindWindowExA(
+                std::ptr::null_mut(),
+                hwnd,
+                secondary.as_ptr(),
+                std::ptr::null(),
+            );
+        }
+    }
+}
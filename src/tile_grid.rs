@@ -25,6 +25,8 @@ pub struct TileGrid {
     pub fullscreen: bool,
     pub focus_stack: Vec<(Direction, i32)>,
     pub tiles: Vec<Tile>,
+    /// Windows on this workspace that don't get tiled, because their rule makes them float.
+    pub floating: Vec<Window>,
     pub focused_window_id: Option<i32>,
     pub taskbar_window: i32,
     pub rows: i32,
@@ -38,6 +40,7 @@ impl TileGrid {
             display: get_primary_display(),
             fullscreen: false,
             tiles: Vec::new(),
+            floating: Vec::new(),
             focus_stack: Vec::with_capacity(5),
             focused_window_id: None,
             taskbar_window: 0,
@@ -49,6 +52,9 @@ impl TileGrid {
         for tile in &self.tiles {
             tile.window.hide();
         }
+        for window in &self.floating {
+            window.hide();
+        }
     }
     pub fn show(&self) {
         for tile in &self.tiles {
@@ -56,9 +62,18 @@ impl TileGrid {
             tile.window
                 .to_foreground(true)
                 .expect("Failed to move window to foreground");
-            tile.window
-                .remove_topmost()
-                .expect("Failed to remove top-most window");
+
+            if !tile.window.rule.as_ref().map_or(false, |r| r.always_on_top) {
+                tile.window
+                    .remove_topmost()
+                    .expect("Failed to remove top-most window");
+            }
+        }
+        for window in &self.floating {
+            window.show();
+            window
+                .to_foreground(window.rule.as_ref().is_some_and(|r| r.always_on_top))
+                .expect("Failed to move window to foreground");
         }
         if let Some(tile) = self.get_focused_tile() {
             tile.window.focus().expect("Failed to focus window");
@@ -71,6 +86,15 @@ impl TileGrid {
             .clone()
             .cloned()
     }
+    pub fn is_floating(&self, id: i32) -> bool {
+        self.floating.iter().any(|window| window.id == id)
+    }
+    pub fn remove_floating(&mut self, id: i32) -> Option<Window> {
+        self.floating
+            .iter()
+            .position(|window| window.id == id)
+            .map(|idx| self.floating.remove(idx))
+    }
     pub fn get_tile_by_id_mut(&mut self, id: i32) -> Option<&mut Tile> {
         self.tiles.iter_mut().find(|tile| tile.window.id == id)
     }
@@ -486,6 +510,10 @@ impl TileGrid {
     }
 
     fn draw_tile(&self, tile: &Tile) {
+        if tile.window.is_placeholder() {
+            return;
+        }
+
         let rect = self.calculate_tile_data(tile);
 
         unsafe {
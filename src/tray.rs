@@ -1,8 +1,11 @@
+use crate::command::Command;
+use crate::command::CommandList;
 use crate::event::Event;
 use crate::util;
 use crate::CHANNEL;
 use crate::CONFIG;
 use lazy_static::lazy_static;
+use log::error;
 use num_traits::FromPrimitive;
 use std::sync::Mutex;
 use winapi::shared::minwindef::HINSTANCE;
@@ -15,10 +18,13 @@ use winapi::shared::windef::HWND;
 use winapi::shared::windef::POINT;
 use winapi::um::shellapi::Shell_NotifyIconW;
 use winapi::um::shellapi::NIF_ICON;
+use winapi::um::shellapi::NIF_INFO;
 use winapi::um::shellapi::NIF_MESSAGE;
 use winapi::um::shellapi::NIF_TIP;
+use winapi::um::shellapi::NIIF_WARNING;
 use winapi::um::shellapi::NIM_ADD;
 use winapi::um::shellapi::NIM_DELETE;
+use winapi::um::shellapi::NIM_MODIFY;
 use winapi::um::shellapi::NOTIFYICONDATAW;
 use winapi::um::wingdi::CreateSolidBrush;
 use winapi::um::winuser::CreateIconFromResourceEx;
@@ -63,6 +69,15 @@ enum PopupId {
     Reload = 1001,
 }
 
+/// The tray runs on its own thread, so its commands get executed by the main loop
+fn run(command: Command) {
+    let commands = CommandList(vec![command]);
+
+    if let Err(e) = CHANNEL.sender.clone().send(Event::Command(commands)) {
+        error!("Failed to send the command of the tray {}", e);
+    }
+}
+
 unsafe extern "system" fn window_cb(
     hwnd: HWND,
     msg: UINT,
@@ -72,24 +87,14 @@ unsafe extern "system" fn window_cb(
     if msg == WM_CREATE {
         add_icon(hwnd);
     } else if msg == WM_CLOSE {
-        CHANNEL
-            .sender
-            .clone()
-            .send(Event::Exit)
-            .expect("Failed to send exit event");
+        run(Command::Quit);
     } else if msg == WM_COMMAND {
         if let Some(id) = PopupId::from_u16(LOWORD(w_param as u32)) {
             match id {
                 PopupId::Exit => {
                     PostMessageW(hwnd, WM_CLOSE, 0, 0);
                 }
-                PopupId::Reload => {
-                    CHANNEL
-                        .sender
-                        .clone()
-                        .send(Event::ReloadConfig)
-                        .expect("Failed to send event");
-                }
+                PopupId::Reload => run(Command::ReloadConfig),
             }
         }
     } else if msg == WM_APP && l_param as u32 == WM_RBUTTONUP {
@@ -190,6 +195,37 @@ pub fn remove_icon(hwnd: HWND) {
     }
 }
 
+/// Copies the text into a fixed size buffer and cuts it off if it doesn't fit
+fn copy_to_widestring_buffer(text: &str, buffer: &mut [u16]) {
+    let len = buffer.len() - 1;
+
+    for (idx, c) in text.encode_utf16().take(len).enumerate() {
+        buffer[idx] = c;
+    }
+}
+
+/// Shows a balloon notification at the tray icon
+pub fn notify(title: &str, message: &str) {
+    let hwnd = *WINDOW.lock().unwrap() as HWND;
+
+    if hwnd.is_null() {
+        return;
+    }
+
+    unsafe {
+        let mut icon_data = NOTIFYICONDATAW::default();
+        icon_data.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+        icon_data.hWnd = hwnd;
+        icon_data.uID = 1;
+        icon_data.uFlags = NIF_INFO;
+        icon_data.dwInfoFlags = NIIF_WARNING;
+        copy_to_widestring_buffer(title, &mut icon_data.szInfoTitle);
+        copy_to_widestring_buffer(message, &mut icon_data.szInfo);
+
+        Shell_NotifyIconW(NIM_MODIFY, &mut icon_data);
+    }
+}
+
 unsafe fn show_popup_menu(hwnd: HWND) {
     let menu = CreatePopupMenu();
 
@@ -1,12 +1,25 @@
 use core::fmt::Debug;
 use thiserror::Error;
+use winapi::shared::minwindef::BOOL;
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::minwindef::LPARAM;
 use winapi::shared::windef::HWND;
 use winapi::shared::windef::RECT;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::winbase::QueryFullProcessImageNameW;
 use winapi::um::wingdi::GetBValue;
 use winapi::um::wingdi::GetGValue;
 use winapi::um::wingdi::GetRValue;
 use winapi::um::wingdi::RGB;
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winuser::EnumWindows;
+use winapi::um::winuser::GetClassNameA;
 use winapi::um::winuser::GetWindowTextA;
+use winapi::um::winuser::GetWindowThreadProcessId;
+use winapi::um::winuser::IsIconic;
+use winapi::um::winuser::IsWindow;
+use winapi::um::winuser::IsWindowVisible;
 
 pub fn get_title_of_window(window_handle: HWND) -> Result<String, WinApiResultError> {
     let mut buffer = [0; 0x200];
@@ -26,6 +39,103 @@ pub fn get_title_of_window(window_handle: HWND) -> Result<String, WinApiResultEr
         .collect::<String>())
 }
 
+pub fn get_class_name_of_window(window_handle: HWND) -> Result<String, WinApiResultError> {
+    let mut buffer = [0; 0x100];
+
+    unsafe {
+        winapi_nullable_to_result(GetClassNameA(
+            window_handle,
+            buffer.as_mut_ptr(),
+            buffer.len() as i32,
+        ))?;
+    };
+
+    Ok(buffer
+        .iter()
+        .take_while(|b| **b != 0)
+        .map(|byte| char::from(*byte as u8))
+        .collect::<String>())
+}
+
+unsafe extern "system" fn enum_windows_cb(hwnd: HWND, l_param: LPARAM) -> BOOL {
+    let windows = &mut *(l_param as *mut Vec<HWND>);
+
+    windows.push(hwnd);
+
+    1
+}
+
+/// Returns every top-level window ordered from the top to the bottom of the z-order.
+pub fn get_top_level_windows() -> Vec<HWND> {
+    let mut windows: Vec<HWND> = Vec::new();
+
+    unsafe {
+        //is synchronous so the vector outlives the callback
+        EnumWindows(
+            Some(enum_windows_cb),
+            &mut windows as *mut Vec<HWND> as LPARAM,
+        );
+    }
+
+    windows
+}
+
+pub fn get_process_id_of_window(window_handle: HWND) -> Result<u32, WinApiResultError> {
+    let mut process_id: DWORD = 0;
+
+    unsafe {
+        GetWindowThreadProcessId(window_handle, &mut process_id);
+    }
+
+    winapi_nullable_to_result(process_id as i32).map(|id| id as u32)
+}
+
+/// Returns the file name of the executable that created the window (e.g. "firefox.exe")
+pub fn get_process_name_of_window(window_handle: HWND) -> Result<String, WinApiResultError> {
+    let process_id = get_process_id_of_window(window_handle)?;
+    let mut buffer = [0u16; 0x400];
+    let mut size = buffer.len() as DWORD;
+
+    unsafe {
+        let process = winapi_ptr_to_result(OpenProcess(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            0,
+            process_id,
+        ))?;
+
+        let result = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut size);
+
+        CloseHandle(process);
+
+        winapi_nullable_to_result(result)?;
+    }
+
+    let path = String::from_utf16_lossy(&buffer[..size as usize]);
+
+    Ok(path.rsplit('\\').next().unwrap_or_default().to_string())
+}
+
+pub fn is_window(window_handle: HWND) -> bool {
+    unsafe { IsWindow(window_handle) != 0 }
+}
+
+pub fn is_window_visible(window_handle: HWND) -> bool {
+    unsafe { IsWindowVisible(window_handle) != 0 }
+}
+
+pub fn is_window_minimized(window_handle: HWND) -> bool {
+    unsafe { IsIconic(window_handle) != 0 }
+}
+
+/// Writes to a temporary file first, so that a crash can't leave a half written file behind
+pub fn write_atomically(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+
+    std::fs::write(&tmp_path, content)?;
+    std::fs::rename(&tmp_path, path)
+}
+
 pub type WinApiResult<T> = Result<T, WinApiResultError>;
 
 #[derive(Debug, Error)]
@@ -17,6 +17,7 @@ use winapi::um::winuser::EVENT_MAX;
 use winapi::um::winuser::EVENT_MIN;
 use winapi::um::winuser::EVENT_OBJECT_DESTROY;
 use winapi::um::winuser::EVENT_OBJECT_HIDE;
+use winapi::um::winuser::EVENT_OBJECT_NAMECHANGE;
 use winapi::um::winuser::EVENT_OBJECT_SHOW;
 use winapi::um::winuser::EVENT_SYSTEM_FOREGROUND;
 use winapi::um::winuser::MSG;
@@ -35,6 +36,7 @@ pub enum WinEventType {
     Hide,
     Show(bool),
     FocusChange,
+    NameChange,
 }
 
 impl WinEventType {
@@ -47,6 +49,8 @@ impl WinEventType {
             Some(Self::FocusChange)
         } else if v == EVENT_OBJECT_HIDE {
             Some(Self::Hide)
+        } else if v == EVENT_OBJECT_NAMECHANGE {
+            Some(Self::NameChange)
         } else {
             None
         }
@@ -1,26 +1,36 @@
+use crate::config::FramePolicy;
 use crate::config::Rule;
+use crate::journal;
 use crate::util;
 use crate::CONFIG;
 use gwl_ex_style::GwlExStyle;
 use gwl_style::GwlStyle;
+use winapi::shared::minwindef::DWORD;
 use winapi::shared::windef::HWND;
 use winapi::shared::windef::RECT;
+use winapi::shared::winerror::S_OK;
+use winapi::um::dwmapi::DwmGetWindowAttribute;
+use winapi::um::dwmapi::DWMWA_CLOAKED;
 use winapi::um::winuser::AdjustWindowRectEx;
 use winapi::um::winuser::GetForegroundWindow;
 use winapi::um::winuser::GetParent;
 use winapi::um::winuser::GetSystemMetrics;
+use winapi::um::winuser::GetWindow;
 use winapi::um::winuser::GetWindowLongA;
 use winapi::um::winuser::GetWindowRect;
 use winapi::um::winuser::SendMessageA;
 use winapi::um::winuser::SetForegroundWindow;
+use winapi::um::winuser::SetLayeredWindowAttributes;
 use winapi::um::winuser::SetWindowLongA;
 use winapi::um::winuser::SetWindowPos;
 use winapi::um::winuser::ShowWindow;
 use winapi::um::winuser::GWL_EXSTYLE;
 use winapi::um::winuser::GWL_STYLE;
+use winapi::um::winuser::GW_OWNER;
 use winapi::um::winuser::HWND_NOTOPMOST;
 use winapi::um::winuser::HWND_TOP;
 use winapi::um::winuser::HWND_TOPMOST;
+use winapi::um::winuser::LWA_ALPHA;
 use winapi::um::winuser::SM_CXFRAME;
 use winapi::um::winuser::SM_CYCAPTION;
 use winapi::um::winuser::SM_CYFRAME;
@@ -30,6 +40,7 @@ use winapi::um::winuser::SW_HIDE;
 use winapi::um::winuser::SW_SHOW;
 use winapi::um::winuser::{SC_MAXIMIZE, SC_RESTORE, WM_CLOSE, WM_SYSCOMMAND};
 
+pub mod eligibility;
 pub mod gwl_ex_style;
 pub mod gwl_style;
 
@@ -42,6 +53,7 @@ pub struct Window {
     pub style: GwlStyle,
     pub exstyle: GwlExStyle,
     pub original_style: GwlStyle,
+    pub original_exstyle: GwlExStyle,
     pub original_rect: RECT,
 }
 
@@ -55,29 +67,56 @@ impl Default for Window {
             style: GwlStyle::default(),
             exstyle: GwlExStyle::default(),
             original_style: GwlStyle::default(),
+            original_exstyle: GwlExStyle::default(),
             original_rect: RECT::default(),
         }
     }
 }
 
 impl Window {
+    /// Placeholders reserve a tile for a window that doesn't exist yet. They use negative ids,
+    /// which never belong to a real window. The methods that talk to the window do nothing for
+    /// placeholders, because ids like -1 have a special meaning as an HWND (`HWND_TOPMOST`).
+    pub fn is_placeholder(&self) -> bool {
+        self.id < 0
+    }
     pub fn reset_style(&mut self) -> Result<(), util::WinApiResultError> {
         self.style = self.original_style;
 
         Ok(())
     }
     pub fn reset(&mut self) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
         self.reset_style()?;
         self.update_style();
+
+        if self.exstyle != self.original_exstyle {
+            self.exstyle = self.original_exstyle;
+            self.update_exstyle();
+        }
+
+        if self.rule.as_ref().map_or(false, |r| r.always_on_top) {
+            self.remove_topmost()?;
+        }
+
         self.reset_pos()?;
 
         if self.maximized {
             self.send_maximize();
         }
 
+        journal::forget(self.id);
+
         Ok(())
     }
     pub fn reset_pos(&self) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
         unsafe {
             util::winapi_nullable_to_result(SetWindowPos(
                 self.id as HWND,
@@ -92,12 +131,83 @@ impl Window {
 
         Ok(())
     }
+    pub fn set_rect(&self, rect: RECT) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
+        unsafe {
+            util::winapi_nullable_to_result(SetWindowPos(
+                self.id as HWND,
+                std::ptr::null_mut(),
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                0,
+            ))?;
+        }
+
+        Ok(())
+    }
+    /// Makes the window translucent. `opacity` has to be between 0 and 1.
+    pub fn set_opacity(&mut self, opacity: f32) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
+        self.exstyle.insert(GwlExStyle::LAYERED);
+        self.update_exstyle();
+
+        unsafe {
+            util::winapi_nullable_to_result(SetLayeredWindowAttributes(
+                self.id as HWND,
+                0,
+                (opacity * 255.0).round() as u8,
+                LWA_ALPHA,
+            ))?;
+        }
+
+        Ok(())
+    }
     pub fn get_foreground_window() -> Result<HWND, util::WinApiResultError> {
         unsafe { util::winapi_ptr_to_result(GetForegroundWindow()) }
     }
     pub fn get_parent_window(&self) -> Result<HWND, util::WinApiResultError> {
         unsafe { util::winapi_ptr_to_result(GetParent(self.id as HWND)) }
     }
+    pub fn get_owner_window(&self) -> Result<HWND, util::WinApiResultError> {
+        unsafe { util::winapi_ptr_to_result(GetWindow(self.id as HWND, GW_OWNER)) }
+    }
+    /// Cloaked windows are invisible even though they have the visible style (e.g. suspended UWP apps).
+    pub fn is_cloaked(&self) -> bool {
+        let mut cloaked: DWORD = 0;
+
+        let result = unsafe {
+            DwmGetWindowAttribute(
+                self.id as HWND,
+                DWMWA_CLOAKED,
+                &mut cloaked as *mut DWORD as *mut _,
+                std::mem::size_of::<DWORD>() as u32,
+            )
+        };
+
+        result == S_OK && cloaked != 0
+    }
+    pub fn get_info(&self) -> Result<eligibility::WindowInfo, util::WinApiResultError> {
+        let rect = self.get_rect()?;
+
+        Ok(eligibility::WindowInfo {
+            class_name: util::get_class_name_of_window(self.id as HWND).unwrap_or_default(),
+            style: self.original_style,
+            exstyle: self.exstyle,
+            has_parent: self.get_parent_window().is_ok(),
+            has_owner: self.get_owner_window().is_ok(),
+            cloaked: self.is_cloaked(),
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+        })
+    }
     pub fn get_style(&self) -> Result<GwlStyle, util::WinApiResultError> {
         unsafe {
             let bits = util::winapi_nullable_to_result(GetWindowLongA(self.id as HWND, GWL_STYLE))?;
@@ -119,11 +229,19 @@ impl Window {
         }
     }
     pub fn show(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             ShowWindow(self.id as HWND, SW_SHOW);
         }
     }
     pub fn hide(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             ShowWindow(self.id as HWND, SW_HIDE);
         }
@@ -139,6 +257,7 @@ impl Window {
                 config.app_bar_height,
             )
         };
+        let keeps_frame = rule.frame_policy(remove_title_bar) == FramePolicy::Keep;
 
         let mut left = x;
         let mut right = x + width;
@@ -149,7 +268,7 @@ impl Window {
             let border_width = GetSystemMetrics(SM_CXFRAME);
             let border_height = GetSystemMetrics(SM_CYFRAME);
 
-            if rule.chromium || rule.firefox || !remove_title_bar {
+            if keeps_frame {
                 let caption_height = GetSystemMetrics(SM_CYCAPTION);
                 top += caption_height;
             } else {
@@ -171,25 +290,20 @@ impl Window {
                 bottom += app_bar_height;
             }
 
-            if rule.firefox || rule.chromium || (!remove_title_bar && rule.has_custom_titlebar) {
-                if rule.firefox {
-                    left -= (border_width as f32 * 1.5) as i32;
-                    right += (border_width as f32 * 1.5) as i32;
-                    bottom += (border_height as f32 * 1.5) as i32;
-                } else if rule.chromium {
-                    left -= border_width * 2;
-                    right += border_width * 2;
-                    bottom += border_height * 2;
-                }
+            if keeps_frame && rule.has_custom_titlebar {
                 left += border_width * 2;
                 right -= border_width * 2;
-                top += border_height * 2;
                 bottom -= border_height * 2;
-            } else {
-                top += border_height * 2;
             }
+
+            top += border_height * 2;
         }
 
+        left += rule.insets.left;
+        top += rule.insets.top;
+        right -= rule.insets.right;
+        bottom -= rule.insets.bottom;
+
         let mut rect = RECT {
             left,
             right,
@@ -213,6 +327,10 @@ impl Window {
         rect
     }
     pub fn to_foreground(&self, topmost: bool) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
         unsafe {
             util::winapi_nullable_to_result(SetWindowPos(
                 self.id as HWND,
@@ -228,6 +346,10 @@ impl Window {
         Ok(())
     }
     pub fn remove_topmost(&self) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
         unsafe {
             util::winapi_nullable_to_result(SetWindowPos(
                 self.id as HWND,
@@ -246,6 +368,10 @@ impl Window {
      * This also brings the window to the foreground
      */
     pub fn focus(&self) -> Result<(), util::WinApiResultError> {
+        if self.is_placeholder() {
+            return Ok(());
+        }
+
         unsafe {
             SetForegroundWindow(self.id as HWND);
         }
@@ -253,37 +379,69 @@ impl Window {
         Ok(())
     }
     pub fn send_close(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             //TODO: Handle Error
             SendMessageA(self.id as HWND, WM_CLOSE, 0, 0);
         }
     }
     pub fn update_style(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             SetWindowLongA(self.id as HWND, GWL_STYLE, self.style.bits());
         }
     }
     pub fn update_exstyle(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             SetWindowLongA(self.id as HWND, GWL_EXSTYLE, self.exstyle.bits());
         }
     }
-    pub fn remove_title_bar(&mut self) {
+    /// Removes the parts of the frame that the matched rule allows to be removed.
+    /// Returns whether the style changed and has to be updated.
+    pub fn apply_frame_policy(&mut self, remove_title_bar: bool) -> bool {
         let rule = self.rule.clone().unwrap_or_default();
-        if !rule.chromium && !rule.firefox {
-            self.style.remove(GwlStyle::CAPTION);
-            self.style.remove(GwlStyle::THICKFRAME);
+
+        match rule.frame_policy(remove_title_bar) {
+            FramePolicy::Keep => return false,
+            FramePolicy::RemoveCaption => {
+                self.style.remove(GwlStyle::CAPTION);
+            }
+            FramePolicy::RemoveAll => {
+                self.style.remove(GwlStyle::CAPTION);
+                self.style.remove(GwlStyle::THICKFRAME);
+            }
         }
+
         self.style.insert(GwlStyle::BORDER);
+
+        true
     }
 
     pub fn send_maximize(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             SendMessageA(self.id as HWND, WM_SYSCOMMAND, SC_MAXIMIZE, 0);
         }
     }
 
     pub fn send_restore(&self) {
+        if self.is_placeholder() {
+            return;
+        }
+
         unsafe {
             SendMessageA(self.id as HWND, WM_SYSCOMMAND, SC_RESTORE, 0);
         }
@@ -0,0 +1,351 @@
+//! Decides whether a window is a normal application window that should get tiled.
+//!
+//! The checks only look at the data collected in [`WindowInfo`], so they don't have to talk to
+//! the Windows api themselves.
+
+use super::gwl_ex_style::GwlExStyle;
+use super::gwl_style::GwlStyle;
+use crate::config::Config;
+
+/// Windows smaller than this (in either dimension) are most likely splash screens or popups.
+pub const MIN_SIZE: i32 = 50;
+
+/// Window classes that never get managed, no matter what the config says.
+const EXCLUDED_CLASSES: [&str; 5] = [
+    "Windows.UI.Core.CoreWindow",
+    "Shell_TrayWnd",
+    "Shell_SecondaryTrayWnd",
+    "Progman",
+    "WorkerW",
+];
+
+/// The window class that windows uses for its standard dialogs.
+const DIALOG_CLASS: &str = "#32770";
+
+#[derive(Debug, Clone)]
+pub struct WindowInfo {
+    pub class_name: String,
+    pub style: GwlStyle,
+    pub exstyle: GwlExStyle,
+    pub has_parent: bool,
+    pub has_owner: bool,
+    pub cloaked: bool,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rejection {
+    Child,
+    Cloaked,
+    ExcludedClass,
+    ToolWindow,
+    Owned,
+    Dialog,
+    NoCaption,
+    TooSmall,
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let reason = match self {
+            Rejection::Child => "it has a parent window",
+            Rejection::Cloaked => "it is cloaked",
+            Rejection::ExcludedClass => "its class is excluded",
+            Rejection::ToolWindow => "it is a tool window",
+            Rejection::Owned => "it is owned by another window",
+            Rejection::Dialog => "it is a dialog",
+            Rejection::NoCaption => "it has neither a caption nor a resizable frame",
+            Rejection::TooSmall => "it is too small",
+        };
+
+        write!(f, "{}", reason)
+    }
+}
+
+/// Checks whether the window should get managed.
+///
+/// `ignore_window_style` skips every check that only looks at how the window presents itself.
+/// This is used when the user explicitly asks wwm to manage a window (e.g. ToggleFloatingMode).
+///
+/// The checks happen in the following order:
+///
+/// 1. Windows with a parent are part of another window.
+/// 2. Cloaked windows are invisible (e.g. suspended UWP apps or windows on another virtual desktop).
+/// 3. Windows whose class is built-in excluded or listed in `excluded_classes`.
+/// 4. Tool windows, unless they ask for a taskbar button with `APPWINDOW`.
+/// 5. Windows with an owner, unless they have `APPWINDOW` or `manage_dialogs` is enabled.
+/// 6. Dialogs (`DLGMODALFRAME` or the standard dialog class), unless `manage_dialogs` is enabled.
+/// 7. Windows without a caption, unless they have `APPWINDOW` or a resizable frame with a minimize
+///    box. The latter catches applications that draw their own title bar.
+/// 8. Windows smaller than [`MIN_SIZE`].
+pub fn check(
+    info: &WindowInfo,
+    config: &Config,
+    ignore_window_style: bool,
+) -> Result<(), Rejection> {
+    if info.has_parent {
+        return Err(Rejection::Child);
+    }
+
+    if info.cloaked {
+        return Err(Rejection::Cloaked);
+    }
+
+    if EXCLUDED_CLASSES.contains(&info.class_name.as_str())
+        || config.excluded_classes.contains(&info.class_name)
+    {
+        return Err(Rejection::ExcludedClass);
+    }
+
+    if ignore_window_style {
+        return Ok(());
+    }
+
+    let app_window = info.exstyle.contains(GwlExStyle::APPWINDOW);
+
+    if info.exstyle.contains(GwlExStyle::TOOLWINDOW) && !app_window {
+        return Err(Rejection::ToolWindow);
+    }
+
+    if info.has_owner && !app_window && !config.manage_dialogs {
+        return Err(Rejection::Owned);
+    }
+
+    if (info.exstyle.contains(GwlExStyle::DLGMODALFRAME) || info.class_name == DIALOG_CLASS)
+        && !config.manage_dialogs
+    {
+        return Err(Rejection::Dialog);
+    }
+
+    let custom_chrome =
+        info.style.contains(GwlStyle::THICKFRAME) && info.style.contains(GwlStyle::MINIMIZEBOX);
+
+    if !info.style.contains(GwlStyle::CAPTION) && !app_window && !custom_chrome {
+        return Err(Rejection::NoCaption);
+    }
+
+    if info.width < MIN_SIZE || info.height < MIN_SIZE {
+        return Err(Rejection::TooSmall);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A normal application window that passes every check
+    fn app() -> WindowInfo {
+        WindowInfo {
+            class_name: String::from("Notepad"),
+            style: GwlStyle::CAPTION | GwlStyle::THICKFRAME | GwlStyle::MINIMIZEBOX,
+            exstyle: GwlExStyle::empty(),
+            has_parent: false,
+            has_owner: false,
+            cloaked: false,
+            width: 800,
+            height: 600,
+        }
+    }
+
+    fn excluding(class_name: &str) -> Config {
+        Config {
+            excluded_classes: vec![class_name.to_string()],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn accepts_application_windows() {
+        assert_eq!(check(&app(), &Config::default(), false), Ok(()));
+    }
+
+    #[test]
+    fn rejects_by_the_first_failing_check() {
+        let config = Config::default();
+        let cases = [
+            (
+                WindowInfo {
+                    has_parent: true,
+                    ..app()
+                },
+                Rejection::Child,
+            ),
+            (
+                WindowInfo {
+                    cloaked: true,
+                    ..app()
+                },
+                Rejection::Cloaked,
+            ),
+            (
+                WindowInfo {
+                    class_name: String::from("Shell_TrayWnd"),
+                    ..app()
+                },
+                Rejection::ExcludedClass,
+            ),
+            (
+                WindowInfo {
+                    exstyle: GwlExStyle::TOOLWINDOW,
+                    ..app()
+                },
+                Rejection::ToolWindow,
+            ),
+            (
+                WindowInfo {
+                    has_owner: true,
+                    ..app()
+                },
+                Rejection::Owned,
+            ),
+            (
+                WindowInfo {
+                    exstyle: GwlExStyle::DLGMODALFRAME,
+                    ..app()
+                },
+                Rejection::Dialog,
+            ),
+            (
+                WindowInfo {
+                    class_name: String::from(DIALOG_CLASS),
+                    ..app()
+                },
+                Rejection::Dialog,
+            ),
+            (
+                WindowInfo {
+                    style: GwlStyle::THICKFRAME,
+                    ..app()
+                },
+                Rejection::NoCaption,
+            ),
+            (
+                WindowInfo {
+                    width: MIN_SIZE - 1,
+                    ..app()
+                },
+                Rejection::TooSmall,
+            ),
+            (
+                WindowInfo {
+                    has_parent: true,
+                    cloaked: true,
+                    ..app()
+                },
+                Rejection::Child,
+            ),
+        ];
+
+        for (info, rejection) in cases.iter() {
+            assert_eq!(check(info, &config, false), Err(*rejection), "{:?}", info);
+        }
+    }
+
+    #[test]
+    fn rejects_classes_of_the_config() {
+        assert_eq!(
+            check(&app(), &excluding("Notepad"), false),
+            Err(Rejection::ExcludedClass)
+        );
+        assert_eq!(
+            check(&app(), &excluding("Notepad"), true),
+            Err(Rejection::ExcludedClass)
+        );
+    }
+
+    #[test]
+    fn app_window_overrides_the_style_checks() {
+        let config = Config::default();
+        let info = WindowInfo {
+            style: GwlStyle::empty(),
+            exstyle: GwlExStyle::TOOLWINDOW | GwlExStyle::APPWINDOW,
+            has_owner: true,
+            ..app()
+        };
+
+        assert_eq!(check(&info, &config, false), Ok(()));
+    }
+
+    #[test]
+    fn manage_dialogs_accepts_owned_windows_and_dialogs() {
+        let config = Config {
+            manage_dialogs: true,
+            ..Config::default()
+        };
+        let info = WindowInfo {
+            class_name: String::from(DIALOG_CLASS),
+            exstyle: GwlExStyle::DLGMODALFRAME,
+            has_owner: true,
+            ..app()
+        };
+
+        assert_eq!(check(&info, &config, false), Ok(()));
+    }
+
+    #[test]
+    fn ignore_window_style_skips_the_style_checks() {
+        let config = Config::default();
+        let cases = [
+            WindowInfo {
+                exstyle: GwlExStyle::TOOLWINDOW,
+                ..app()
+            },
+            WindowInfo {
+                has_owner: true,
+                ..app()
+            },
+            WindowInfo {
+                exstyle: GwlExStyle::DLGMODALFRAME,
+                ..app()
+            },
+            WindowInfo {
+                style: GwlStyle::empty(),
+                ..app()
+            },
+            WindowInfo {
+                width: 1,
+                height: 1,
+                ..app()
+            },
+        ];
+
+        for info in cases.iter() {
+            assert_eq!(check(info, &config, true), Ok(()), "{:?}", info);
+        }
+    }
+
+    #[test]
+    fn ignore_window_style_keeps_the_other_checks() {
+        let config = Config::default();
+        let cases = [
+            (
+                WindowInfo {
+                    has_parent: true,
+                    ..app()
+                },
+                Rejection::Child,
+            ),
+            (
+                WindowInfo {
+                    cloaked: true,
+                    ..app()
+                },
+                Rejection::Cloaked,
+            ),
+            (
+                WindowInfo {
+                    class_name: String::from("Progman"),
+                    ..app()
+                },
+                Rejection::ExcludedClass,
+            ),
+        ];
+
+        for (info, rejection) in cases.iter() {
+            assert_eq!(check(info, &config, true), Err(*rejection), "{:?}", info);
+        }
+    }
+}